@@ -0,0 +1,244 @@
+//! Reads Anki `.apkg` packages into deck files, one per top-level Anki deck.
+//!
+//! Nested Anki decks (`Parent::Child`) become stems of the `Parent` branch. Each Anki card
+//! becomes a leaf whose scheduling state is rebuilt by replaying its review log through FSRS;
+//! cards Anki still has scheduled keep the due date Anki gave them.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    io::{Cursor, Read},
+};
+
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use serde::Deserialize;
+use sqlx::{
+    sqlite::{SqliteConnectOptions, SqliteConnection},
+    Connection,
+};
+use thiserror::Error;
+use zip::ZipArchive;
+
+use crate::{
+    cloze,
+    deck_file::{rating, DeckEntry, DeckFile, LeafEntry},
+    Config, Leaf,
+};
+
+const COLLECTIONS: [&str; 2] = ["collection.anki21", "collection.anki2"];
+const FIELD_SEPARATOR: char = '\x1f';
+
+#[derive(Debug, Error)]
+pub enum AnkiError {
+    #[error("The package holds no Anki collection")]
+    MissingCollection,
+    #[error(transparent)]
+    Zip(#[from] zip::result::ZipError),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    Database(#[from] sqlx::Error),
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+}
+
+#[derive(sqlx::FromRow)]
+struct AnkiCard {
+    id: i64,
+    nid: i64,
+    did: i64,
+    ord: i64,
+    #[sqlx(rename = "type")]
+    kind: i64,
+    due: i64,
+}
+
+#[derive(sqlx::FromRow)]
+struct AnkiReview {
+    id: i64,
+    cid: i64,
+    ease: i64,
+    #[sqlx(rename = "type")]
+    kind: i64,
+}
+
+#[derive(Deserialize)]
+struct AnkiDeckJson {
+    name: String,
+}
+
+pub async fn read_apkg(package: &[u8]) -> Result<Vec<DeckFile>, AnkiError> {
+    let mut archive = ZipArchive::new(Cursor::new(package))?;
+
+    let name = COLLECTIONS
+        .into_iter()
+        .find(|name| archive.by_name(name).is_ok())
+        .ok_or(AnkiError::MissingCollection)?;
+
+    let mut collection = Vec::new();
+    archive.by_name(name)?.read_to_end(&mut collection)?;
+
+    // SQLite can only open collections from disk.
+    let file = tempfile::NamedTempFile::new()?;
+    std::fs::write(file.path(), collection)?;
+
+    let options = SqliteConnectOptions::new()
+        .filename(file.path())
+        .read_only(true);
+    let mut connection = SqliteConnection::connect_with(&options).await?;
+
+    let files = read_collection(&mut connection).await;
+    connection.close().await?;
+
+    files
+}
+
+async fn read_collection(connection: &mut SqliteConnection) -> Result<Vec<DeckFile>, AnkiError> {
+    let created_at: i64 = sqlx::query_scalar("SELECT crt FROM col")
+        .fetch_one(&mut *connection)
+        .await?;
+    let created_at = Utc
+        .timestamp_opt(created_at, 0)
+        .single()
+        .unwrap_or_default();
+
+    let decks = read_decks(connection).await?;
+
+    let notes: HashMap<i64, String> =
+        sqlx::query_as::<_, (i64, String)>("SELECT id, flds FROM notes")
+            .fetch_all(&mut *connection)
+            .await?
+            .into_iter()
+            .collect();
+
+    let mut reviews: HashMap<i64, Vec<AnkiReview>> = HashMap::new();
+    for review in
+        sqlx::query_as::<_, AnkiReview>("SELECT id, cid, ease, type FROM revlog ORDER BY id")
+            .fetch_all(&mut *connection)
+            .await?
+    {
+        reviews.entry(review.cid).or_default().push(review);
+    }
+
+    let cards =
+        sqlx::query_as::<_, AnkiCard>("SELECT id, nid, did, ord, type, due FROM cards ORDER BY id")
+            .fetch_all(&mut *connection)
+            .await?;
+
+    let config = Config::default();
+    let mut branches: BTreeMap<String, BTreeMap<String, Vec<LeafEntry>>> = BTreeMap::new();
+    for card in cards {
+        let Some(fields) = notes.get(&card.nid) else {
+            continue;
+        };
+        let deck = decks.get(&card.did).cloned().unwrap_or_default();
+        let (branch, stem) = match deck.split_once("::") {
+            Some((branch, stem)) => (branch.to_string(), stem.to_string()),
+            None => (deck.clone(), deck),
+        };
+
+        let leaf = card.to_leaf(
+            fields,
+            reviews.get(&card.id).map_or(&[][..], Vec::as_slice),
+            created_at,
+            &config,
+        );
+
+        branches
+            .entry(branch)
+            .or_default()
+            .entry(stem)
+            .or_default()
+            .push(LeafEntry::from(&leaf));
+    }
+
+    Ok(branches
+        .into_iter()
+        .map(|(branch, stems)| {
+            let decks = stems
+                .into_iter()
+                .map(|(name, leaves)| DeckEntry { name, leaves })
+                .collect();
+
+            DeckFile::new(Some(branch), decks)
+        })
+        .collect())
+}
+
+/// Collections from Anki 2.1.28 onwards keep decks in their own table, older ones as JSON in `col`.
+async fn read_decks(connection: &mut SqliteConnection) -> Result<HashMap<i64, String>, AnkiError> {
+    if let Ok(decks) = sqlx::query_as::<_, (i64, String)>("SELECT id, name FROM decks")
+        .fetch_all(&mut *connection)
+        .await
+    {
+        return Ok(decks
+            .into_iter()
+            .map(|(id, name)| (id, name.replace(FIELD_SEPARATOR, "::")))
+            .collect());
+    }
+
+    let json: String = sqlx::query_scalar("SELECT decks FROM col")
+        .fetch_one(&mut *connection)
+        .await?;
+
+    Ok(
+        serde_json::from_str::<HashMap<String, AnkiDeckJson>>(&json)?
+            .into_iter()
+            .filter_map(|(id, deck)| id.parse().ok().map(|id| (id, deck.name)))
+            .collect(),
+    )
+}
+
+impl AnkiCard {
+    /// Builds the leaf shown by this card. The second template of a note (`ord` 1) is taken to be
+    /// the reverse of the first, as in Anki's "Basic (and reversed card)" note type, unless the
+    /// note is a cloze, whose cards ask for the cloze numbered `ord + 1` and keep the note together.
+    fn to_leaf(
+        &self,
+        fields: &str,
+        reviews: &[AnkiReview],
+        created_at: DateTime<Utc>,
+        config: &Config,
+    ) -> Leaf {
+        let mut fields = fields.split(FIELD_SEPARATOR);
+        let first = fields.next().unwrap_or_default();
+        let second = fields.next().unwrap_or_default();
+        let cloze = u32::try_from(self.ord + 1)
+            .ok()
+            .filter(|number| cloze::numbers(first).contains(number));
+        let (front, back) = if self.ord == 1 && cloze.is_none() {
+            (second, first)
+        } else {
+            (first, second)
+        };
+
+        let mut leaf = Leaf::new(front, back, created_at);
+        leaf.cloze = cloze;
+        leaf.note = cloze.map(|_| self.nid.to_string());
+
+        // Manual reschedules (type 4) and cram reviews without an answer (ease 0) are not ratings.
+        for review in reviews.iter().filter(|review| review.kind != 4) {
+            let (Some(rating), Some(now)) = (
+                u32::try_from(review.ease).ok().and_then(rating),
+                Utc.timestamp_millis_opt(review.id).single(),
+            ) else {
+                continue;
+            };
+
+            leaf.review(config, rating, now);
+        }
+
+        // Review cards are due a number of days after the collection was created, learning cards
+        // at a timestamp.
+        match self.kind {
+            2 => leaf.card.due = created_at + Duration::days(self.due),
+            1 | 3 => {
+                if let Some(due) = Utc.timestamp_opt(self.due, 0).single() {
+                    leaf.card.due = due;
+                }
+            }
+            _ => {}
+        }
+
+        leaf
+    }
+}
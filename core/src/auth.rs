@@ -28,6 +28,27 @@ cfg_if::cfg_if! { if #[cfg(feature = "auth")] {
     use sqlx::SqlitePool;
     pub type AuthSession = axum_session_auth::AuthSession<User, i64, SessionSqlitePool, SqlitePool>;
 
+    #[derive(Debug, thiserror::Error)]
+    pub enum AccessError {
+        #[error("Unauthorized")]
+        Unauthorized,
+        #[error("Forbidden")]
+        Forbidden,
+        #[error("Not Found")]
+        NotFound,
+        #[error(transparent)]
+        Database(sqlx::Error),
+    }
+
+    impl From<sqlx::Error> for AccessError {
+        fn from(error: sqlx::Error) -> Self {
+            match error {
+                sqlx::Error::RowNotFound => AccessError::NotFound,
+                error => AccessError::Database(error),
+            }
+        }
+    }
+
     impl User {
         pub async fn get(id: i64, pool: &SqlitePool) -> Option<Self> {
             let sqluser = sqlx::query_as::<_, SqlUser>("SELECT * FROM users WHERE id = ?")
@@ -66,6 +87,53 @@ cfg_if::cfg_if! { if #[cfg(feature = "auth")] {
 
             Some(sqluser.into_user(Some(sql_user_perms)))
         }
+
+        pub async fn authorize_branch(&self, id: u32, pool: &SqlitePool) -> Result<(), AccessError> {
+            let owner_id = sqlx::query_scalar::<_, i64>("SELECT user_id FROM branches WHERE id = ?")
+                .bind(id)
+                .fetch_one(pool)
+                .await?;
+
+            self.authorize(owner_id)
+        }
+
+        pub async fn authorize_stem(&self, id: u32, pool: &SqlitePool) -> Result<(), AccessError> {
+            let owner_id = sqlx::query_scalar::<_, i64>(
+                "SELECT b.user_id FROM stems s
+                    INNER JOIN branches b
+                        ON b.id = s.branch_id
+                    WHERE s.id = ?",
+            )
+            .bind(id)
+            .fetch_one(pool)
+            .await?;
+
+            self.authorize(owner_id)
+        }
+
+        pub async fn authorize_leaf(&self, id: u32, pool: &SqlitePool) -> Result<(), AccessError> {
+            let owner_id = sqlx::query_scalar::<_, i64>(
+                "SELECT b.user_id FROM leaves l
+                    INNER JOIN stems s
+                        ON s.id = l.stem_id
+                    INNER JOIN branches b
+                        ON b.id = s.branch_id
+                    WHERE l.id = ?",
+            )
+            .bind(id)
+            .fetch_one(pool)
+            .await?;
+
+            self.authorize(owner_id)
+        }
+
+        fn authorize(&self, owner_id: i64) -> Result<(), AccessError> {
+            if self.id == owner_id {
+                Ok(())
+            } else {
+                Err(AccessError::Forbidden)
+            }
+        }
     }
 
     #[derive(sqlx::FromRow, Clone)]
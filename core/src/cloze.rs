@@ -0,0 +1,139 @@
+//! Cloze leaves hide parts of their front behind `{{c1::answer}}` markers, optionally with a hint
+//! shown in the blank: `{{c1::answer::hint}}`. Each number is scheduled as a leaf of its own, and
+//! markers sharing a number are blanked together.
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Segment {
+    Text(String),
+    /// The cloze being asked for, before it is revealed, with its hint if any.
+    Blank(Option<String>),
+    /// The cloze being asked for, once revealed.
+    Answer(String),
+}
+
+struct Marker<'a> {
+    number: u32,
+    answer: &'a str,
+    hint: Option<&'a str>,
+}
+
+enum Part<'a> {
+    Text(&'a str),
+    Cloze(Marker<'a>),
+}
+
+/// The distinct cloze numbers of `text`, in ascending order.
+pub fn numbers(text: &str) -> Vec<u32> {
+    let mut numbers: Vec<u32> = parse(text)
+        .into_iter()
+        .filter_map(|part| match part {
+            Part::Cloze(marker) => Some(marker.number),
+            Part::Text(_) => None,
+        })
+        .collect();
+    numbers.sort_unstable();
+    numbers.dedup();
+
+    numbers
+}
+
+/// Splits `text` as shown when asking for cloze `number`: its markers are blanked until
+/// `revealed`, and the other ones read as plain text.
+pub fn segments(text: &str, number: u32, revealed: bool) -> Vec<Segment> {
+    let mut segments: Vec<Segment> = Vec::new();
+
+    for part in parse(text) {
+        let segment = match part {
+            Part::Cloze(marker) if marker.number == number => {
+                segments.push(if revealed {
+                    Segment::Answer(marker.answer.to_string())
+                } else {
+                    Segment::Blank(marker.hint.map(str::to_string))
+                });
+                continue;
+            }
+            Part::Cloze(marker) => marker.answer,
+            Part::Text(text) => text,
+        };
+
+        match segments.last_mut() {
+            Some(Segment::Text(previous)) => previous.push_str(segment),
+            _ => segments.push(Segment::Text(segment.to_string())),
+        }
+    }
+
+    segments
+}
+
+/// `text` as plain text when asking for cloze `number`, blanks reading `[...]` or `[hint]`.
+pub fn render(text: &str, number: u32, revealed: bool) -> String {
+    segments(text, number, revealed)
+        .into_iter()
+        .map(|segment| match segment {
+            Segment::Text(text) | Segment::Answer(text) => text,
+            Segment::Blank(hint) => format!("[{}]", hint.as_deref().unwrap_or("...")),
+        })
+        .collect()
+}
+
+fn parse(mut text: &str) -> Vec<Part<'_>> {
+    let mut parts = Vec::new();
+
+    while let Some(start) = text.find("{{c") {
+        let Some(end) = closing(&text[start + 3..]).map(|end| start + 3 + end) else {
+            break;
+        };
+
+        match marker(&text[start + 3..end]) {
+            Some(marker) => {
+                if start > 0 {
+                    parts.push(Part::Text(&text[..start]));
+                }
+                parts.push(Part::Cloze(marker));
+            }
+            None => parts.push(Part::Text(&text[..end + 2])),
+        }
+        text = &text[end + 2..];
+    }
+
+    if !text.is_empty() {
+        parts.push(Part::Text(text));
+    }
+
+    parts
+}
+
+/// Where the `}}` closing a marker is in `inner`, past its `{{c`. Braces within the marker are
+/// matched first, so answers such as `\sqrt{2}` can end right before it.
+fn closing(inner: &str) -> Option<usize> {
+    let mut depth = 0;
+
+    for (i, c) in inner.char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' if depth > 0 => depth -= 1,
+            '}' if inner[i + 1..].starts_with('}') => return Some(i),
+            _ => {}
+        }
+    }
+
+    None
+}
+
+/// Parses the inside of a marker, past its `{{c`.
+fn marker(inner: &str) -> Option<Marker<'_>> {
+    let (number, rest) = inner.split_once("::")?;
+    let number = number.parse().ok().filter(|&number| number > 0)?;
+    let (answer, hint) = match rest.split_once("::") {
+        Some((answer, hint)) => (answer, Some(hint)),
+        None => (rest, None),
+    };
+
+    Some(Marker {
+        number,
+        answer,
+        hint,
+    })
+}
@@ -0,0 +1,253 @@
+//! How leaves are scheduled: the FSRS weights of a user, and the retention target, maximum interval
+//! and learning steps of a branch.
+
+use chrono::Duration;
+use fsrs::{Card, Parameters, State, FSRS};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::Rating;
+
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    #[error("Desired retention must be between 0.7 and 0.99")]
+    DesiredRetention,
+    #[error("Maximum interval must be between 1 and 36500 days")]
+    MaximumInterval,
+    #[error("Invalid learning step \"{0}\"")]
+    LearningStep(String),
+    #[error("Each learning step must be longer than the one before")]
+    LearningStepOrder,
+    #[cfg(feature = "auth")]
+    #[error(transparent)]
+    Database(#[from] sqlx::Error),
+}
+
+pub struct Config {
+    pub fsrs: FSRS,
+    /// How long a leaf in (re)learning waits before its next review, in place of the FSRS default.
+    pub learning_steps: Vec<Duration>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self::new(Parameters::default().w, &BranchConfig::default())
+    }
+}
+
+impl Config {
+    pub fn new(w: [f64; 17], branch: &BranchConfig) -> Self {
+        Self {
+            fsrs: FSRS::new(Parameters {
+                w,
+                request_retention: branch.desired_retention,
+                maximum_interval: branch.maximum_interval,
+            }),
+            learning_steps: branch
+                .learning_steps
+                .iter()
+                .map(|&minutes| Duration::minutes(minutes.into()))
+                .collect(),
+        }
+    }
+
+    /// How long a leaf whose schedule was `card` waits after `rating` while it is (re)learning, or
+    /// `None` once it graduates. Again goes back to the first step, Hard waits halfway to the next
+    /// step, Good moves on to the next step and Easy graduates straight away.
+    pub fn learning_step(&self, card: &Card, rating: Rating) -> Option<Duration> {
+        let first = *self.learning_steps.first()?;
+        let current = match card.state {
+            State::New => 0,
+            State::Learning | State::Relearning => self.current_step(card),
+            State::Review if rating == Rating::Again => return Some(first),
+            State::Review => return None,
+        };
+
+        let step = self.learning_steps[current];
+        let next = self.learning_steps.get(current + 1).copied();
+
+        match rating {
+            Rating::Again => Some(first),
+            Rating::Hard => Some(next.map_or(step * 3 / 2, |next| (step + next) / 2)),
+            Rating::Good => next,
+            Rating::Easy => None,
+        }
+    }
+
+    /// The step a (re)learning card is on: the longest one it was last scheduled to wait for.
+    fn current_step(&self, card: &Card) -> usize {
+        let waited = card.due - card.last_review;
+
+        self.learning_steps
+            .iter()
+            .rposition(|&step| step <= waited)
+            .unwrap_or(0)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BranchConfig {
+    pub desired_retention: f64,
+    /// In days.
+    pub maximum_interval: i32,
+    /// In minutes.
+    pub learning_steps: Vec<u32>,
+}
+
+impl Default for BranchConfig {
+    fn default() -> Self {
+        Self {
+            desired_retention: 0.9,
+            maximum_interval: 36500,
+            learning_steps: vec![1, 10],
+        }
+    }
+}
+
+impl BranchConfig {
+    /// Reads a configuration as typed in a form, with steps such as `1m 10m 1h 1d`.
+    pub fn parse(
+        desired_retention: &str,
+        maximum_interval: &str,
+        learning_steps: &str,
+    ) -> Result<Self, ConfigError> {
+        let config = Self {
+            desired_retention: desired_retention
+                .trim()
+                .parse()
+                .map_err(|_| ConfigError::DesiredRetention)?,
+            maximum_interval: maximum_interval
+                .trim()
+                .parse()
+                .map_err(|_| ConfigError::MaximumInterval)?,
+            learning_steps: parse_steps(learning_steps)?,
+        };
+        config.check()?;
+
+        Ok(config)
+    }
+
+    /// Checks that the configuration can be saved and read back.
+    pub fn check(&self) -> Result<(), ConfigError> {
+        if !(0.7..=0.99).contains(&self.desired_retention) {
+            return Err(ConfigError::DesiredRetention);
+        }
+        if !(1..=36500).contains(&self.maximum_interval) {
+            return Err(ConfigError::MaximumInterval);
+        }
+        if let Some(&step) = self.learning_steps.iter().find(|&&step| step == 0) {
+            return Err(ConfigError::LearningStep(format!("{step}m")));
+        }
+        if self
+            .learning_steps
+            .windows(2)
+            .any(|steps| steps[0] >= steps[1])
+        {
+            return Err(ConfigError::LearningStepOrder);
+        }
+
+        Ok(())
+    }
+
+    pub fn format_steps(&self) -> String {
+        self.learning_steps
+            .iter()
+            .map(|&minutes| match minutes {
+                minutes if minutes % 1440 == 0 => format!("{}d", minutes / 1440),
+                minutes if minutes % 60 == 0 => format!("{}h", minutes / 60),
+                minutes => format!("{minutes}m"),
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+/// Writes an interval the way it is shown on rating buttons, such as `10m`, `3d` or `1.5y`.
+pub fn format_interval(interval: Duration) -> String {
+    let minutes = interval.num_seconds().max(0) as f64 / 60.0;
+    let days = minutes / 1440.0;
+
+    match minutes {
+        minutes if minutes < 60.0 => format!("{}m", minutes.round().max(1.0)),
+        minutes if minutes < 1440.0 => format!("{}h", (minutes / 60.0).round()),
+        _ if days < 30.0 => format!("{}d", days.round()),
+        _ if days < 365.0 => format!("{}mo", (days / 30.0).round()),
+        _ => format!("{}y", (days / 36.5).round() / 10.0),
+    }
+}
+
+fn parse_steps(steps: &str) -> Result<Vec<u32>, ConfigError> {
+    steps
+        .split_whitespace()
+        .map(|step| {
+            let (value, unit) =
+                step.split_at(step.len() - step.chars().last().map_or(0, char::len_utf8));
+            let minutes = match unit {
+                "m" => 1,
+                "h" => 60,
+                "d" => 1440,
+                _ => return Err(ConfigError::LearningStep(step.to_string())),
+            };
+
+            value
+                .parse::<u32>()
+                .ok()
+                .filter(|&value| value > 0)
+                .and_then(|value| value.checked_mul(minutes))
+                .ok_or_else(|| ConfigError::LearningStep(step.to_string()))
+        })
+        .collect()
+}
+
+cfg_if::cfg_if! { if #[cfg(feature = "auth")] {
+    use sqlx::SqlitePool;
+
+    use crate::optimizer::weights;
+
+    impl Config {
+        /// The configuration `leaf_id` is scheduled with: its owner's weights and its branch's settings.
+        pub async fn for_leaf(user_id: i64, leaf_id: u32, pool: &SqlitePool) -> Result<Self, sqlx::Error> {
+            let (desired_retention, maximum_interval, learning_steps) = sqlx::query_as::<_, (f64, i32, String)>(
+                "SELECT b.desired_retention, b.maximum_interval, b.learning_steps FROM leaves l
+                    INNER JOIN stems s
+                        ON s.id = l.stem_id
+                    INNER JOIN branches b
+                        ON b.id = s.branch_id
+                    WHERE l.id = ?",
+            )
+            .bind(leaf_id)
+            .fetch_one(pool)
+            .await?;
+
+            let branch = BranchConfig::from_columns(desired_retention, maximum_interval, &learning_steps);
+            let w = weights(user_id, pool).await?.unwrap_or(Parameters::default().w);
+
+            Ok(Config::new(w, &branch))
+        }
+    }
+
+    impl BranchConfig {
+        /// Reads a configuration saved by `update`, which checks the steps before they are saved.
+        pub fn from_columns(desired_retention: f64, maximum_interval: i32, learning_steps: &str) -> Self {
+            Self {
+                desired_retention,
+                maximum_interval,
+                learning_steps: parse_steps(learning_steps).unwrap_or_default(),
+            }
+        }
+
+        pub async fn update(&self, branch_id: u32, pool: &SqlitePool) -> Result<(), ConfigError> {
+            self.check()?;
+
+            sqlx::query("UPDATE branches SET desired_retention = ?, maximum_interval = ?, learning_steps = ? WHERE id = ?")
+                .bind(self.desired_retention)
+                .bind(self.maximum_interval)
+                .bind(self.format_steps())
+                .bind(branch_id)
+                .execute(pool)
+                .await?;
+
+            Ok(())
+        }
+    }
+}}
@@ -0,0 +1,259 @@
+//! Reads and writes the leaves of a stem as CSV or TSV, one leaf per row.
+//!
+//! Fields follow RFC 4180 quoting: a field containing the delimiter, a quote or a line break is
+//! wrapped in double quotes, and quotes inside it are doubled. The `front` and `back` columns are
+//! required; `due` (RFC 3339 or `YYYY-MM-DD`), `stability` and `difficulty` are optional. A row with
+//! a stability is imported as a review card last reviewed at import time.
+
+use std::str::FromStr;
+
+use chrono::{DateTime, NaiveDate, Utc};
+use fsrs::State;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{deck_file::LeafEntry, Card, Leaf};
+
+#[derive(Debug, Error)]
+pub enum CsvError {
+    #[error("Missing {0} column")]
+    MissingColumn(&'static str),
+    #[error("Unknown column \"{0}\"")]
+    UnknownColumn(String),
+    #[error(transparent)]
+    Csv(#[from] csv::Error),
+    #[error(transparent)]
+    Utf8(#[from] std::string::FromUtf8Error),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Column {
+    Front,
+    Back,
+    Due,
+    Stability,
+    Difficulty,
+    Ignore,
+}
+
+impl FromStr for Column {
+    type Err = CsvError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "front" => Ok(Column::Front),
+            "back" => Ok(Column::Back),
+            "due" => Ok(Column::Due),
+            "stability" => Ok(Column::Stability),
+            "difficulty" => Ok(Column::Difficulty),
+            "" | "-" | "ignore" => Ok(Column::Ignore),
+            other => Err(CsvError::UnknownColumn(other.to_string())),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CsvOptions {
+    pub delimiter: u8,
+    pub has_headers: bool,
+    /// Maps each column of the file, in order. When missing, columns are read from the headers,
+    /// or default to `front, back`.
+    pub columns: Option<Vec<Column>>,
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        Self {
+            delimiter: b',',
+            has_headers: true,
+            columns: None,
+        }
+    }
+}
+
+impl CsvOptions {
+    pub fn tsv() -> Self {
+        Self {
+            delimiter: b'\t',
+            ..Default::default()
+        }
+    }
+
+    pub fn parse_columns(mapping: &str) -> Result<Vec<Column>, CsvError> {
+        mapping.split(',').map(Column::from_str).collect()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RowError {
+    pub line: u64,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CsvPreview {
+    pub leaves: Vec<LeafEntry>,
+    pub errors: Vec<RowError>,
+}
+
+pub fn read_leaves(content: &str, options: &CsvOptions) -> Result<CsvPreview, CsvError> {
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(options.delimiter)
+        .has_headers(options.has_headers)
+        .flexible(true)
+        .from_reader(content.as_bytes());
+
+    let columns = match &options.columns {
+        Some(columns) => columns.clone(),
+        None if options.has_headers => reader
+            .headers()?
+            .iter()
+            .map(|header| Column::from_str(header).unwrap_or(Column::Ignore))
+            .collect(),
+        None => vec![Column::Front, Column::Back],
+    };
+
+    if !columns.contains(&Column::Front) {
+        return Err(CsvError::MissingColumn("front"));
+    }
+    if !columns.contains(&Column::Back) {
+        return Err(CsvError::MissingColumn("back"));
+    }
+
+    let now = Utc::now();
+    let mut preview = CsvPreview::default();
+    for record in reader.records() {
+        let result = record.map_err(|error| RowError {
+            line: error.position().map_or(0, |position| position.line()),
+            message: error.to_string(),
+        });
+
+        match result.and_then(|record| read_leaf(&record, &columns, now)) {
+            Ok(leaf) => preview.leaves.push(leaf),
+            Err(error) => preview.errors.push(error),
+        }
+    }
+
+    Ok(preview)
+}
+
+fn read_leaf(
+    record: &csv::StringRecord,
+    columns: &[Column],
+    now: DateTime<Utc>,
+) -> Result<LeafEntry, RowError> {
+    let line = record.position().map_or(0, |position| position.line());
+    let error = |message: String| RowError { line, message };
+
+    let mut front = None;
+    let mut back = None;
+    let mut due = None;
+    let mut stability = None;
+    let mut difficulty = None;
+
+    for (column, field) in columns.iter().zip(record.iter()) {
+        let field = field.trim();
+        if field.is_empty() {
+            continue;
+        }
+
+        match column {
+            Column::Front => front = Some(field.to_string()),
+            Column::Back => back = Some(field.to_string()),
+            Column::Due => {
+                due = Some(
+                    parse_due(field)
+                        .ok_or_else(|| error(format!("Invalid due date \"{field}\"")))?,
+                )
+            }
+            Column::Stability => match field.parse::<f64>() {
+                Ok(value) if value > 0.0 => stability = Some(value),
+                _ => return Err(error(format!("Invalid stability \"{field}\""))),
+            },
+            Column::Difficulty => match field.parse::<f64>() {
+                Ok(value) if (1.0..=10.0).contains(&value) => difficulty = Some(value),
+                _ => return Err(error(format!("Invalid difficulty \"{field}\""))),
+            },
+            Column::Ignore => {}
+        }
+    }
+
+    // The back column is required, but a cloze leaf may well leave it empty.
+    let front = front.ok_or_else(|| error("Missing front".to_string()))?;
+    let back = back.unwrap_or_default();
+
+    let card = if due.is_none() && stability.is_none() && difficulty.is_none() {
+        None
+    } else {
+        let mut card = Card::default();
+        if let Some(due) = due {
+            card.due = due;
+        }
+        if let Some(stability) = stability {
+            card.stability = stability;
+            card.state = State::Review;
+            card.last_review = now;
+            card.scheduled_days = (card.due - now).num_days().max(0);
+        }
+        if let Some(difficulty) = difficulty {
+            card.difficulty = difficulty;
+        }
+        Some(card)
+    };
+
+    Ok(LeafEntry {
+        front,
+        back,
+        card,
+        cloze: None,
+        note: None,
+        bidirectional: false,
+        reverse_card: None,
+    })
+}
+
+fn parse_due(field: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(field)
+        .map(|due| due.with_timezone(&Utc))
+        .ok()
+        .or_else(|| {
+            NaiveDate::parse_from_str(field, "%Y-%m-%d")
+                .ok()?
+                .and_hms_opt(0, 0, 0)
+                .map(|due| due.and_utc())
+        })
+}
+
+pub fn write_leaves(leaves: &[Leaf], delimiter: u8) -> Result<String, CsvError> {
+    let mut writer = csv::WriterBuilder::new()
+        .delimiter(delimiter)
+        .from_writer(Vec::new());
+
+    writer.write_record(["front", "back", "due", "stability", "difficulty"])?;
+    for leaf in leaves {
+        let card = leaf.card();
+        if card.state == State::New {
+            writer.write_record([
+                leaf.front(),
+                leaf.back(),
+                String::new(),
+                String::new(),
+                String::new(),
+            ])?;
+        } else {
+            writer.write_record([
+                leaf.front(),
+                leaf.back(),
+                card.due.to_rfc3339(),
+                card.stability.to_string(),
+                card.difficulty.to_string(),
+            ])?;
+        }
+    }
+
+    let content = writer.into_inner().map_err(|error| error.into_error())?;
+
+    Ok(String::from_utf8(content)?)
+}
@@ -0,0 +1,389 @@
+//! Deck files are how decks leave and enter Brainace: the desktop app saves them, and the web
+//! garden imports and exports them. The same serde types are written as RON or JSON.
+//!
+//! ```ron
+//! (
+//!     version: 1,
+//!     branch: None,
+//!     decks: [
+//!         (
+//!             name: "Mathematical Constants",
+//!             leaves: [
+//!                 (front: "The first five digits of Pi are [...]", back: "3.1415", card: None),
+//!             ],
+//!         ),
+//!     ],
+//! )
+//! ```
+//!
+//! `card` holds the FSRS scheduling state of a leaf and may be omitted for new leaves, as may
+//! `cloze`, the cloze of the front a leaf asks for: a new leaf with `{{c1::...}}` markers and no
+//! `cloze` grows into one leaf per cloze, all sharing a `note`. `bidirectional` leaves are also
+//! asked from back to front, with their own `reverse_card`.
+//!
+//! Files written before versioning
+//! (`Some((name, cards: [(card: (front, back, fsrs_item, last_review))]))`) are still read: their
+//! review history is replayed through FSRS to rebuild each card.
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{Card, Config, Deck, Leaf, Rating};
+
+pub const VERSION: u32 = 1;
+
+#[derive(Debug, Error)]
+pub enum DeckFileError {
+    #[error("Unsupported deck file version {0}")]
+    UnsupportedVersion(u32),
+    #[error(transparent)]
+    Ron(#[from] ron::Error),
+    #[error(transparent)]
+    RonSpanned(#[from] ron::error::SpannedError),
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeckFile {
+    pub version: u32,
+    #[serde(default)]
+    pub branch: Option<String>,
+    pub decks: Vec<DeckEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeckEntry {
+    pub name: String,
+    pub leaves: Vec<LeafEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LeafEntry {
+    pub front: String,
+    pub back: String,
+    #[serde(default)]
+    pub card: Option<Card>,
+    /// The cloze of `front` the leaf asks for. New entries without one get a leaf per cloze.
+    #[serde(default)]
+    pub cloze: Option<u32>,
+    /// The note shared by the leaves of the other clozes of `front`, which are edited together.
+    /// Notes are only told apart within a file: every import gives them new ones.
+    #[serde(default)]
+    pub note: Option<String>,
+    /// Whether the leaf is also asked from back to front, on the schedule of `reverse_card`. Ignored
+    /// for cloze leaves.
+    #[serde(default)]
+    pub bidirectional: bool,
+    #[serde(default)]
+    pub reverse_card: Option<Card>,
+}
+
+impl DeckFile {
+    pub fn new(branch: Option<String>, decks: Vec<DeckEntry>) -> Self {
+        Self {
+            version: VERSION,
+            branch,
+            decks,
+        }
+    }
+
+    /// Reads a deck file, guessing JSON or RON from its first character.
+    pub fn parse(content: &str) -> Result<Self, DeckFileError> {
+        if content.trim_start().starts_with('{') {
+            Self::from_json(content)
+        } else {
+            Self::from_ron(content)
+        }
+    }
+
+    pub fn from_ron(content: &str) -> Result<Self, DeckFileError> {
+        match ron::from_str::<DeckFile>(content) {
+            Ok(file) => file.checked(),
+            Err(error) => ron::from_str::<Option<LegacyDeck>>(content)
+                .map(|legacy| legacy.map_or_else(|| Self::new(None, Vec::new()), Self::from))
+                .map_err(|_| error.into()),
+        }
+    }
+
+    pub fn from_json(content: &str) -> Result<Self, DeckFileError> {
+        serde_json::from_str::<DeckFile>(content)?.checked()
+    }
+
+    pub fn to_ron(&self) -> Result<String, DeckFileError> {
+        Ok(ron::ser::to_string_pretty(
+            self,
+            ron::ser::PrettyConfig::default(),
+        )?)
+    }
+
+    pub fn to_json(&self) -> Result<String, DeckFileError> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn into_decks(self, now: DateTime<Utc>) -> Vec<Deck> {
+        self.decks
+            .into_iter()
+            .map(|entry| entry.into_deck(now))
+            .collect()
+    }
+
+    fn checked(self) -> Result<Self, DeckFileError> {
+        if self.version > VERSION {
+            Err(DeckFileError::UnsupportedVersion(self.version))
+        } else {
+            Ok(self)
+        }
+    }
+}
+
+impl From<&Deck> for DeckFile {
+    fn from(deck: &Deck) -> Self {
+        Self::new(None, vec![deck.into()])
+    }
+}
+
+impl DeckEntry {
+    pub fn into_deck(self, now: DateTime<Utc>) -> Deck {
+        Deck {
+            name: self.name,
+            leaves: grow_leaves(self.leaves, now),
+        }
+    }
+}
+
+impl From<&Deck> for DeckEntry {
+    fn from(deck: &Deck) -> Self {
+        Self {
+            name: deck.name.clone(),
+            leaves: deck.leaves.iter().map(LeafEntry::from).collect(),
+        }
+    }
+}
+
+impl LeafEntry {
+    pub fn into_leaf(self, now: DateTime<Utc>) -> Leaf {
+        let mut leaf = Leaf::new(&self.front, &self.back, now);
+        leaf.card = self.card.unwrap_or_default();
+        leaf.cloze = self.cloze;
+        leaf.note = self.cloze.and(self.note);
+        leaf.reverse_card = (self.bidirectional && self.cloze.is_none())
+            .then(|| self.reverse_card.unwrap_or_default());
+        leaf
+    }
+
+    /// Like [`LeafEntry::into_leaf`], but a new entry is grown into one leaf per cloze of its front.
+    pub fn into_leaves(self, now: DateTime<Utc>) -> Vec<Leaf> {
+        if self.card.is_none() && self.cloze.is_none() && !self.bidirectional {
+            Leaf::grow(&self.front, &self.back, now)
+        } else {
+            vec![self.into_leaf(now)]
+        }
+    }
+}
+
+impl From<&Leaf> for LeafEntry {
+    fn from(leaf: &Leaf) -> Self {
+        Self {
+            front: leaf.front(),
+            back: leaf.back(),
+            card: Some(leaf.card().clone()),
+            cloze: leaf.cloze(),
+            note: leaf.note().map(str::to_string),
+            bidirectional: leaf.is_bidirectional(),
+            reverse_card: leaf.reverse_card().cloned(),
+        }
+    }
+}
+
+/// The leaves of `entries`, each note of the file growing into a new note.
+fn grow_leaves(entries: Vec<LeafEntry>, now: DateTime<Utc>) -> Vec<Leaf> {
+    let mut leaves: Vec<Leaf> = entries
+        .into_iter()
+        .flat_map(|entry| entry.into_leaves(now))
+        .collect();
+    Leaf::renote(&mut leaves, now);
+
+    leaves
+}
+
+pub(crate) fn rating(value: u32) -> Option<Rating> {
+    match value {
+        1 => Some(Rating::Again),
+        2 => Some(Rating::Hard),
+        3 => Some(Rating::Good),
+        4 => Some(Rating::Easy),
+        _ => None,
+    }
+}
+
+#[derive(Deserialize)]
+struct LegacyDeck {
+    name: String,
+    cards: Vec<LegacyCardEntry>,
+}
+
+#[derive(Deserialize)]
+struct LegacyCardEntry {
+    card: LegacyCard,
+}
+
+#[derive(Deserialize)]
+struct LegacyCard {
+    front: String,
+    back: String,
+    fsrs_item: LegacyItem,
+    last_review: Option<DateTime<Utc>>,
+}
+
+#[derive(Deserialize)]
+struct LegacyItem {
+    reviews: Vec<LegacyReview>,
+}
+
+#[derive(Deserialize)]
+struct LegacyReview {
+    rating: u32,
+    delta_t: u32,
+}
+
+impl From<LegacyDeck> for DeckFile {
+    fn from(legacy: LegacyDeck) -> Self {
+        let leaves = legacy
+            .cards
+            .into_iter()
+            .map(|entry| LeafEntry::from(entry.card))
+            .collect();
+
+        Self::new(
+            None,
+            vec![DeckEntry {
+                name: legacy.name,
+                leaves,
+            }],
+        )
+    }
+}
+
+impl From<LegacyCard> for LeafEntry {
+    fn from(legacy: LegacyCard) -> Self {
+        Self {
+            card: legacy.replay(),
+            front: legacy.front,
+            back: legacy.back,
+            cloze: None,
+            note: None,
+            bidirectional: false,
+            reverse_card: None,
+        }
+    }
+}
+
+impl LegacyCard {
+    /// Rebuilds the card by replaying the review history, whose `delta_t` are the days elapsed
+    /// since the previous review, ending at `last_review`.
+    fn replay(&self) -> Option<Card> {
+        let last_review = self.last_review?;
+        let reviews = &self.fsrs_item.reviews;
+
+        let mut times = vec![last_review; reviews.len()];
+        for i in (1..reviews.len()).rev() {
+            times[i - 1] = times[i] - Duration::days(reviews[i].delta_t.into());
+        }
+
+        let config = Config::default();
+        let mut leaf = Leaf::default();
+        for (review, now) in reviews.iter().zip(times) {
+            if let Some(rating) = rating(review.rating) {
+                leaf.review(&config, rating, now);
+            }
+        }
+
+        Some(leaf.card)
+    }
+}
+
+cfg_if::cfg_if! { if #[cfg(feature = "auth")] {
+    use sqlx::{SqliteConnection, SqlitePool};
+
+    use crate::{SqlBranch, SqlStem, Stem};
+
+    impl DeckFile {
+        pub async fn from_branch(id: u32, pool: &SqlitePool) -> Option<Self> {
+            let branch = sqlx::query_as::<_, SqlBranch>("SELECT * FROM branches WHERE id = ?")
+                .bind(id)
+                .fetch_one(pool)
+                .await
+                .ok()?;
+            let stems = sqlx::query_as::<_, SqlStem>("SELECT * FROM stems WHERE branch_id = ?")
+                .bind(id)
+                .fetch_all(pool)
+                .await
+                .ok()?;
+
+            let mut decks = Vec::with_capacity(stems.len());
+            for stem in stems {
+                let leaves = Stem::get_leaves(stem.id.into(), pool).await?;
+                decks.push(DeckEntry::from(&Deck::from_stem(&stem.into_stem(), leaves)));
+            }
+
+            Some(Self::new(Some(branch.name), decks))
+        }
+
+        pub async fn from_stem(id: u32, pool: &SqlitePool) -> Option<Self> {
+            Deck::get(id.into(), pool).await.map(|deck| Self::from(&deck))
+        }
+
+        /// Grows a new branch for `user_id` holding one stem per deck, in a single transaction.
+        pub async fn insert(&self, user_id: i64, now: DateTime<Utc>, pool: &SqlitePool) -> Result<u32, sqlx::Error> {
+            let mut transaction = pool.begin().await?;
+            let branch_id = self.grow(user_id, now, &mut transaction).await?;
+            transaction.commit().await?;
+
+            Ok(branch_id)
+        }
+
+        /// Grows a new branch per file for `user_id`, all in a single transaction, so a failure
+        /// leaves none of them behind.
+        pub async fn insert_all(files: &[Self], user_id: i64, now: DateTime<Utc>, pool: &SqlitePool) -> Result<Vec<u32>, sqlx::Error> {
+            let mut transaction = pool.begin().await?;
+            let mut branch_ids = Vec::with_capacity(files.len());
+            for file in files {
+                branch_ids.push(file.grow(user_id, now, &mut transaction).await?);
+            }
+            transaction.commit().await?;
+
+            Ok(branch_ids)
+        }
+
+        async fn grow(&self, user_id: i64, now: DateTime<Utc>, connection: &mut SqliteConnection) -> Result<u32, sqlx::Error> {
+            let name = match &self.branch {
+                Some(name) => name.clone(),
+                None => self.decks.first().map_or("Imported".to_string(), |deck| deck.name.clone()),
+            };
+
+            let branch_id = sqlx::query("INSERT INTO branches (user_id, name) VALUES (?, ?)")
+                .bind(user_id)
+                .bind(name)
+                .execute(&mut *connection)
+                .await?
+                .last_insert_rowid();
+
+            for deck in &self.decks {
+                let stem_id = sqlx::query("INSERT INTO stems (branch_id, name) VALUES (?, ?)")
+                    .bind(branch_id)
+                    .bind(&deck.name)
+                    .execute(&mut *connection)
+                    .await?
+                    .last_insert_rowid();
+
+                for leaf in grow_leaves(deck.leaves.clone(), now) {
+                    leaf.insert(stem_id as u32, &mut *connection).await?;
+                }
+            }
+
+            Ok(branch_id as u32)
+        }
+    }
+}}
@@ -1,17 +1,32 @@
+#[cfg(feature = "anki")]
+pub mod anki;
 pub mod auth;
+pub mod cloze;
+pub mod config;
+pub mod csv_file;
+pub mod deck_file;
+pub mod markup;
+pub mod media;
+pub mod optimizer;
+pub mod queue;
+pub mod review_log;
+pub mod settings;
+pub mod stats;
 
-pub use fsrs::{Card, Rating};
+pub use config::Config;
+pub use fsrs::{Card, Rating, State};
 
-use chrono::{DateTime, Utc};
-use fsrs::FSRS;
+use std::{
+    collections::HashMap,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use chrono::{DateTime, Duration, Utc};
 use serde::{Deserialize, Serialize};
 
 use auth::User;
-
-#[derive(Default)]
-pub struct Config {
-    pub fsrs: FSRS,
-}
+use config::BranchConfig;
+use markup::Markup;
 
 #[derive(Clone, Serialize, Deserialize)]
 pub struct Branch {
@@ -19,6 +34,7 @@ pub struct Branch {
     user: Option<User>,
     name: String,
     created_at: String,
+    config: BranchConfig,
 }
 
 impl Branch {
@@ -29,6 +45,10 @@ impl Branch {
     pub fn name(&self) -> String {
         self.name.clone()
     }
+
+    pub fn config(&self) -> &BranchConfig {
+        &self.config
+    }
 }
 
 #[derive(Default, Clone, Serialize, Deserialize)]
@@ -56,6 +76,42 @@ impl Stem {
     }
 }
 
+/// Which way a leaf is asked: front to back, or back to front for the reverse of a bidirectional
+/// leaf.
+#[derive(
+    Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+pub enum Direction {
+    #[default]
+    Forward,
+    Reverse,
+}
+
+impl Direction {
+    pub fn from_reversed(reversed: bool) -> Self {
+        if reversed {
+            Self::Reverse
+        } else {
+            Self::Forward
+        }
+    }
+
+    pub fn is_reverse(self) -> bool {
+        self == Self::Reverse
+    }
+}
+
+/// What [`Leaf::edit`] did to the leaves of the edited note.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LeafEdit {
+    /// Leaves grown for the clozes added to the front.
+    pub grown: usize,
+    /// Leaves pruned, with their schedule and reviews, as their cloze was taken out of the front.
+    pub pruned: usize,
+    /// Whether the edited leaf was among the pruned, with no new cloze to take over.
+    pub edited_pruned: bool,
+}
+
 #[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct Leaf {
     id: u32,
@@ -64,6 +120,25 @@ pub struct Leaf {
     back: String,
     created_at: String,
     card: Card,
+    /// The cloze of the front this leaf asks for, `None` for a basic leaf.
+    #[serde(default)]
+    cloze: Option<u32>,
+    /// The note the clozes grown from the same front share, so they are edited together. `None`
+    /// for a basic leaf.
+    #[serde(default)]
+    note: Option<String>,
+    /// The schedule of the back to front direction, `None` unless the leaf is bidirectional.
+    #[serde(default)]
+    reverse_card: Option<Card>,
+    /// The direction the leaf is asked in, as picked by the due queue.
+    #[serde(default)]
+    direction: Direction,
+    /// Counts every change to the schedule, so a review of a leaf changed in the meantime is refused.
+    #[serde(default)]
+    version: u32,
+    /// The sides rendered to HTML by the server, for the web client.
+    #[serde(default)]
+    markup: Option<Markup>,
 }
 
 impl Leaf {
@@ -76,9 +151,81 @@ impl Leaf {
         }
     }
 
+    /// One leaf per cloze of `text`, each scheduled on its own.
+    pub fn clozes(text: &str, back: &str, now: DateTime<Utc>) -> Vec<Self> {
+        let note = Self::new_note(now);
+        cloze::numbers(text)
+            .into_iter()
+            .map(|number| Self {
+                cloze: Some(number),
+                note: Some(note.clone()),
+                ..Self::new(text, back, now)
+            })
+            .collect()
+    }
+
+    /// The leaves of a new front and back: one per cloze if the front has any, or a basic leaf.
+    pub fn grow(front: &str, back: &str, now: DateTime<Utc>) -> Vec<Self> {
+        let clozes = Self::clozes(front, back, now);
+        if clozes.is_empty() {
+            vec![Self::new(front, back, now)]
+        } else {
+            clozes
+        }
+    }
+
+    /// A note no other front has grown, for the leaves of its clozes.
+    fn new_note(now: DateTime<Utc>) -> String {
+        static GROWN: AtomicU64 = AtomicU64::new(0);
+
+        format!(
+            "{:x}-{:x}-{:x}",
+            now.timestamp_micros(),
+            std::process::id(),
+            GROWN.fetch_add(1, Ordering::Relaxed)
+        )
+    }
+
+    /// Gives the leaves of each note a new note of their own, so leaves read from a file are not
+    /// edited together with those of an earlier import.
+    pub(crate) fn renote(leaves: &mut [Self], now: DateTime<Utc>) {
+        let mut notes = HashMap::new();
+        for note in leaves.iter_mut().filter_map(|leaf| leaf.note.as_mut()) {
+            *note = notes
+                .entry(note.clone())
+                .or_insert_with(|| Self::new_note(now))
+                .clone();
+        }
+    }
+
+    /// Reviews the card of the direction the leaf is asked in.
     pub fn review(&mut self, config: &Config, rating: Rating, now: DateTime<Utc>) {
-        let scheduled_cards = config.fsrs.schedule(self.card.clone(), now);
-        self.card = scheduled_cards.select_card(rating);
+        let card = self.asked_card_mut();
+        let step = config.learning_step(card, rating);
+        let state = card.state;
+        let scheduled_cards = config.fsrs.schedule(card.clone(), now);
+        *card = scheduled_cards.select_card(rating);
+
+        // The leaf stays in (re)learning until it is past the last step, even if FSRS would let it
+        // graduate sooner.
+        if let Some(step) = step {
+            card.state = match state {
+                State::Review | State::Relearning => State::Relearning,
+                State::New | State::Learning => State::Learning,
+            };
+            card.due = now + step;
+            card.scheduled_days = step.num_days();
+        }
+    }
+
+    /// How long the leaf would wait after each rating, from Again to Easy.
+    pub fn intervals(&self, config: &Config, now: DateTime<Utc>) -> [Duration; 4] {
+        [Rating::Again, Rating::Hard, Rating::Good, Rating::Easy].map(|rating| {
+            let mut leaf = self.clone();
+            leaf.review(config, rating, now);
+
+            leaf.asked_card().due - now
+        })
     }
 
     pub fn id(&self) -> u32 {
@@ -101,10 +248,96 @@ impl Leaf {
         self.created_at.clone()
     }
 
+    /// The schedule of the front to back direction.
     pub fn card(&self) -> &Card {
         &self.card
     }
 
+    pub fn reverse_card(&self) -> Option<&Card> {
+        self.reverse_card.as_ref()
+    }
+
+    /// The schedule of the direction the leaf is asked in.
+    pub fn asked_card(&self) -> &Card {
+        match self.direction {
+            Direction::Reverse => self.reverse_card.as_ref().unwrap_or(&self.card),
+            Direction::Forward => &self.card,
+        }
+    }
+
+    fn asked_card_mut(&mut self) -> &mut Card {
+        match (self.direction, &mut self.reverse_card) {
+            (Direction::Reverse, Some(card)) => card,
+            _ => &mut self.card,
+        }
+    }
+
+    pub fn is_bidirectional(&self) -> bool {
+        self.reverse_card.is_some()
+    }
+
+    /// Gives the leaf a new schedule from back to front, or drops it. Cloze leaves are only asked
+    /// from front to back.
+    pub fn set_bidirectional(&mut self, bidirectional: bool) {
+        let bidirectional = bidirectional && self.cloze.is_none();
+        self.reverse_card = bidirectional.then(|| self.reverse_card.take().unwrap_or_default());
+    }
+
+    pub fn direction(&self) -> Direction {
+        self.direction
+    }
+
+    /// The same leaf asked in `direction`, which falls back to forward for leaves that are not
+    /// bidirectional.
+    pub fn asked(mut self, direction: Direction) -> Self {
+        self.direction = if self.is_bidirectional() {
+            direction
+        } else {
+            Direction::Forward
+        };
+        self
+    }
+
+    pub fn cloze(&self) -> Option<u32> {
+        self.cloze
+    }
+
+    pub fn note(&self) -> Option<&str> {
+        self.note.as_deref()
+    }
+
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    /// Catches up with a change stored since the leaf was loaded, such as an undone review, so it
+    /// can still be reviewed.
+    pub fn set_version(&mut self, version: u32) {
+        self.version = version;
+    }
+
+    pub fn markup(&self) -> Option<&Markup> {
+        self.markup.as_ref()
+    }
+
+    /// The side the leaf is asked with: cloze leaves blank their cloze until `revealed`, and the
+    /// reverse direction asks with the back.
+    pub fn prompt(&self, revealed: bool) -> String {
+        match (self.direction, self.cloze) {
+            (Direction::Reverse, _) => self.back(),
+            (Direction::Forward, Some(number)) => cloze::render(&self.front, number, revealed),
+            (Direction::Forward, None) => self.front(),
+        }
+    }
+
+    /// The side the leaf is answered with.
+    pub fn answer(&self) -> String {
+        match self.direction {
+            Direction::Reverse => self.front(),
+            Direction::Forward => self.back(),
+        }
+    }
+
     pub fn set_front(&mut self, front: &str) {
         self.front = front.to_string();
     }
@@ -114,8 +347,36 @@ impl Leaf {
     }
 }
 
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Deck {
+    pub name: String,
+    pub leaves: Vec<Leaf>,
+}
+
+impl Deck {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            ..Default::default()
+        }
+    }
+
+    pub fn from_stem(stem: &Stem, leaves: Vec<Leaf>) -> Self {
+        Self {
+            name: stem.name(),
+            leaves,
+        }
+    }
+
+    pub fn review(&mut self, i: usize, config: &Config, rating: Rating, now: DateTime<Utc>) {
+        if let Some(leaf) = self.leaves.get_mut(i) {
+            leaf.review(config, rating, now);
+        }
+    }
+}
+
 cfg_if::cfg_if! { if #[cfg(feature = "auth")] {
-    use sqlx::{FromRow, SqlitePool};
+    use sqlx::{FromRow, SqliteConnection, SqlitePool};
 
     #[derive(FromRow)]
     pub struct SqlBranch {
@@ -123,11 +384,20 @@ cfg_if::cfg_if! { if #[cfg(feature = "auth")] {
         pub user_id: i64,
         pub name: String,
         pub created_at: String,
+        pub desired_retention: f64,
+        pub maximum_interval: i32,
+        pub learning_steps: String,
     }
 
     impl SqlBranch {
         pub async fn into_branch(&self, pool: &SqlitePool) -> Branch {
-            Branch { id: self.id, user: User::get(self.user_id, pool).await, name: self.name.clone(), created_at: self.created_at.clone() }
+            Branch {
+                id: self.id,
+                user: User::get(self.user_id, pool).await,
+                name: self.name.clone(),
+                created_at: self.created_at.clone(),
+                config: BranchConfig::from_columns(self.desired_retention, self.maximum_interval, &self.learning_steps),
+            }
         }
     }
 
@@ -138,6 +408,132 @@ cfg_if::cfg_if! { if #[cfg(feature = "auth")] {
                 .fetch_all(pool)
                 .await.ok().map(|sql_leaves| sql_leaves.iter().map(|sql_leaf| sql_leaf.into_leaf()).collect())
         }
+
+        pub async fn add_leaves(stem_id: u32, leaves: &[Leaf], pool: &SqlitePool) -> Result<(), sqlx::Error> {
+            let mut transaction = pool.begin().await?;
+
+            for leaf in leaves {
+                leaf.insert(stem_id, &mut transaction).await?;
+            }
+
+            transaction.commit().await
+        }
+    }
+
+    impl Leaf {
+        pub async fn get(id: u32, pool: &SqlitePool) -> Result<Self, sqlx::Error> {
+            Ok(sqlx::query_as::<_, SqlLeaf>("SELECT * FROM leaves WHERE id = ?")
+                .bind(id)
+                .fetch_one(pool)
+                .await?
+                .into_leaf())
+        }
+
+        pub async fn insert(&self, stem_id: u32, connection: &mut SqliteConnection) -> Result<i64, sqlx::Error> {
+            sqlx::query("INSERT INTO leaves (stem_id, front, back, card, cloze, note, reverse_card) VALUES (?, ?, ?, ?, ?, ?, ?)")
+                .bind(stem_id)
+                .bind(&self.front)
+                .bind(&self.back)
+                .bind(sqlx::types::Json(&self.card))
+                .bind(self.cloze)
+                .bind(&self.note)
+                .bind(self.reverse_card.as_ref().map(sqlx::types::Json))
+                .execute(connection)
+                .await
+                .map(|result| result.last_insert_rowid())
+        }
+
+        /// Rewrites the front and back of leaf `id`, keeping its schedule. The clozes of its note are
+        /// rewritten along: clozes added to the front grow leaves of their own, and those taken out
+        /// of it are pruned, unless the edited leaf can take over a new one.
+        pub async fn edit(id: u32, front: &str, back: &str, now: DateTime<Utc>, pool: &SqlitePool) -> Result<LeafEdit, sqlx::Error> {
+            let leaf = Self::get(id, pool).await?;
+            let mut transaction = pool.begin().await?;
+
+            let siblings = match &leaf.note {
+                Some(note) => {
+                    sqlx::query_as::<_, (u32, Option<u32>)>("SELECT id, cloze FROM leaves WHERE stem_id = ? AND note = ?")
+                        .bind(leaf.stem_id)
+                        .bind(note)
+                        .fetch_all(&mut *transaction)
+                        .await?
+                }
+                None => vec![(id, leaf.cloze)],
+            };
+            let note = leaf.note.clone().unwrap_or_else(|| Self::new_note(now));
+
+            let mut clozes: Vec<Option<u32>> = cloze::numbers(front).into_iter().map(Some).collect();
+            if clozes.is_empty() {
+                clozes.push(None);
+            }
+            let mut grown: Vec<Option<u32>> = clozes
+                .iter()
+                .filter(|&cloze| !siblings.iter().any(|(_, sibling)| sibling == cloze))
+                .copied()
+                .collect();
+
+            let mut edit = LeafEdit::default();
+            for (sibling, cloze) in siblings {
+                let kept = if clozes.contains(&cloze) {
+                    Some(cloze)
+                } else if sibling == id && !grown.is_empty() {
+                    Some(grown.remove(0))
+                } else {
+                    None
+                };
+
+                match kept {
+                    Some(cloze) => {
+                        // Cloze leaves are only asked from front to back, so a leaf that becomes one
+                        // drops its reverse schedule.
+                        sqlx::query(
+                            "UPDATE leaves SET front = $1, back = $2, cloze = $3, note = $4,
+                                reverse_card = CASE WHEN $3 IS NULL THEN reverse_card END,
+                                version = CASE WHEN $3 IS NULL OR reverse_card IS NULL THEN version ELSE version + 1 END
+                                WHERE id = $5",
+                        )
+                        .bind(front)
+                        .bind(back)
+                        .bind(cloze)
+                        .bind(cloze.map(|_| &note))
+                        .bind(sibling)
+                        .execute(&mut *transaction)
+                        .await?;
+                    }
+                    None => {
+                        sqlx::query("DELETE FROM leaves WHERE id = ?")
+                            .bind(sibling)
+                            .execute(&mut *transaction)
+                            .await?;
+                        edit.pruned += 1;
+                        edit.edited_pruned |= sibling == id;
+                    }
+                }
+            }
+
+            edit.grown = grown.len();
+            for cloze in grown {
+                let note = cloze.map(|_| note.clone());
+                Leaf { cloze, note, ..Leaf::new(front, back, now) }
+                    .insert(leaf.stem_id, &mut transaction)
+                    .await?;
+            }
+
+            transaction.commit().await?;
+
+            Ok(edit)
+        }
+    }
+
+    impl Deck {
+        pub async fn get(stem_id: i64, pool: &SqlitePool) -> Option<Self> {
+            let stem = sqlx::query_as::<_, SqlStem>("SELECT * FROM stems WHERE id = ?")
+                .bind(stem_id)
+                .fetch_one(pool)
+                .await.ok()?.into_stem();
+
+            Some(Deck::from_stem(&stem, Stem::get_leaves(stem_id, pool).await?))
+        }
     }
 
     #[derive(FromRow)]
@@ -161,7 +557,14 @@ cfg_if::cfg_if! { if #[cfg(feature = "auth")] {
         front: String,
         back: String,
         card: sqlx::types::Json<Card>,
+        cloze: Option<u32>,
+        note: Option<String>,
+        reverse_card: Option<sqlx::types::Json<Card>>,
         created_at: String,
+        version: u32,
+        /// Only selected by the due queue, which asks for each direction on its own.
+        #[sqlx(default)]
+        reversed: bool,
     }
 
     impl SqlLeaf {
@@ -172,6 +575,12 @@ cfg_if::cfg_if! { if #[cfg(feature = "auth")] {
                 front: self.front.clone(),
                 back: self.back.clone(),
                 card: self.card.0.clone(),
+                cloze: self.cloze,
+                note: self.note.clone(),
+                reverse_card: self.reverse_card.as_ref().map(|card| card.0.clone()),
+                direction: Direction::from_reversed(self.reversed),
+                markup: None,
+                version: self.version,
                 created_at: self.created_at.clone()
             }
         }
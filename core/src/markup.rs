@@ -0,0 +1,136 @@
+//! The sides of a leaf are written in Markdown, with fenced code blocks and `$...$` or `$$...$$`
+//! math. They are rendered to HTML and sanitized on the server, so the web client can show them as
+//! they are. Math is left in `math` spans for KaTeX to typeset in the browser, and attached
+//! [`media`](crate::media) is shown where it is referred to.
+
+use serde::{Deserialize, Serialize};
+
+/// The sides of a leaf as sanitized HTML, in the direction it is asked in.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Markup {
+    /// The side the leaf is asked with, its cloze blanked.
+    pub prompt: String,
+    /// The same side once revealed, its cloze filled in.
+    pub revealed: String,
+    /// The side the leaf is answered with.
+    pub answer: String,
+}
+
+cfg_if::cfg_if! { if #[cfg(feature = "markup")] {
+    use ammonia::Builder;
+    use pulldown_cmark::{html, CowStr, Event, Options, Parser, Tag, TagEnd};
+
+    use crate::{cloze::{self, Segment}, media::{self, Kind}, Direction, Leaf};
+
+    /// Renders Markdown to sanitized HTML. Raw HTML is allowed in the Markdown, but only what is
+    /// safe of it is kept.
+    pub fn render(text: &str) -> String {
+        let options = Options::ENABLE_MATH | Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH;
+        let mut html = String::new();
+        html::push_html(&mut html, with_media(Parser::new_ext(text, options)).into_iter());
+
+        Builder::default()
+            .add_allowed_classes("span", &["math", "math-inline", "math-display", "cloze"])
+            .add_tags(&["audio"])
+            .add_tag_attributes("audio", &["controls", "src"])
+            .clean(&html)
+            .to_string()
+    }
+
+    /// Points `media:` images to where they are served, and turns audio clips into players.
+    fn with_media<'a>(events: impl Iterator<Item = Event<'a>>) -> Vec<Event<'a>> {
+        let mut rendered = Vec::new();
+        let mut in_audio = false;
+
+        for event in events {
+            match event {
+                Event::Start(Tag::Image { link_type, dest_url, title, id }) => match media::source(&dest_url) {
+                    Some((src, Kind::Audio)) => {
+                        in_audio = true;
+                        rendered.push(Event::InlineHtml(format!(r#"<audio controls src="{src}"></audio>"#).into()));
+                    }
+                    Some((src, Kind::Image)) => rendered.push(Event::Start(Tag::Image {
+                        link_type,
+                        dest_url: CowStr::from(src),
+                        title,
+                        id,
+                    })),
+                    None => rendered.push(Event::Start(Tag::Image { link_type, dest_url, title, id })),
+                },
+                // The alt text of an audio clip has nowhere to go.
+                Event::End(TagEnd::Image) if in_audio => in_audio = false,
+                _ if in_audio => {}
+                event => rendered.push(event),
+            }
+        }
+
+        rendered
+    }
+
+    /// Renders `text` as asked for cloze `number`, its blanks or answers in `cloze` spans. Within math,
+    /// where HTML can't go, they are boxed instead: `\boxed{?}` or the hint, then the answer.
+    pub fn render_cloze(text: &str, number: u32, revealed: bool) -> String {
+        let mut in_math = false;
+        let marked: String = cloze::segments(text, number, revealed)
+            .into_iter()
+            .map(|segment| match segment {
+                Segment::Text(text) => {
+                    in_math = ends_in_math(&text, in_math);
+                    text
+                }
+                Segment::Blank(hint) if in_math => format!(r"\boxed{{{}}}", hint.as_deref().unwrap_or("?")),
+                Segment::Answer(answer) if in_math => format!(r"\boxed{{{answer}}}"),
+                Segment::Blank(hint) => format!(r#"<span class="cloze">[{}]</span>"#, hint.as_deref().unwrap_or("...")),
+                Segment::Answer(answer) => format!(r#"<span class="cloze">{answer}</span>"#),
+            })
+            .collect();
+
+        render(&marked)
+    }
+
+    /// Whether math is still open after `text`, given whether it was before: each `$` or `$$` opens
+    /// or closes it, unless escaped.
+    fn ends_in_math(text: &str, mut in_math: bool) -> bool {
+        let mut chars = text.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => {
+                    chars.next();
+                }
+                '$' => {
+                    chars.next_if_eq(&'$');
+                    in_math = !in_math;
+                }
+                _ => {}
+            }
+        }
+
+        in_math
+    }
+
+    impl Leaf {
+        /// The leaf with its [`Markup`] rendered, to be sent to the web client.
+        pub fn with_markup(mut self) -> Self {
+            self.markup = Some(self.render_markup());
+            self
+        }
+
+        pub fn render_markup(&self) -> Markup {
+            match (self.direction, self.cloze) {
+                (Direction::Forward, Some(number)) => Markup {
+                    prompt: render_cloze(&self.front, number, false),
+                    revealed: render_cloze(&self.front, number, true),
+                    answer: render(&self.back),
+                },
+                _ => {
+                    let prompt = render(&self.prompt(true));
+                    Markup {
+                        revealed: prompt.clone(),
+                        prompt,
+                        answer: render(&self.answer()),
+                    }
+                }
+            }
+        }
+    }
+}}
@@ -0,0 +1,204 @@
+//! Images and audio clips attached to leaves. Files are stored under the SHA-256 hash of their
+//! content, so a file uploaded twice is only stored once, and each user keeps a row for the files
+//! they uploaded. Leaves refer to them from their Markdown as `![name](media:<hash>.<extension>)`,
+//! which is rendered as an image or an audio player served from `/media/<hash>.<extension>`.
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+/// The largest file that can be attached, in bytes.
+pub const MAX_SIZE: usize = 10 * 1024 * 1024;
+
+#[derive(Debug, Error)]
+pub enum MediaError {
+    #[error("Only images and audio clips can be attached, not \"{0}\"")]
+    Unsupported(String),
+    #[error("Attachments must be smaller than 10 MiB")]
+    TooLarge,
+    #[error("The attachment is empty")]
+    Empty,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Kind {
+    Image,
+    Audio,
+}
+
+/// The types that can be attached, with the extension they are referred to with. SVG is left
+/// out, as it can carry scripts.
+const TYPES: [(&str, &str, Kind); 9] = [
+    ("image/png", "png", Kind::Image),
+    ("image/jpeg", "jpg", Kind::Image),
+    ("image/gif", "gif", Kind::Image),
+    ("image/webp", "webp", Kind::Image),
+    ("audio/mpeg", "mp3", Kind::Audio),
+    ("audio/ogg", "ogg", Kind::Audio),
+    ("audio/wav", "wav", Kind::Audio),
+    ("audio/webm", "weba", Kind::Audio),
+    ("audio/mp4", "m4a", Kind::Audio),
+];
+
+/// A file uploaded by a user.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Media {
+    /// The SHA-256 hash of the content, in lowercase hex.
+    pub hash: String,
+    pub mime: String,
+    /// The name of the file as it was uploaded.
+    pub name: String,
+    pub size: u32,
+}
+
+impl Media {
+    pub fn kind(&self) -> Option<Kind> {
+        kind(&self.mime).map(|(_, kind)| kind)
+    }
+
+    /// The name the file is served under: `<hash>.<extension>`.
+    pub fn file_name(&self) -> String {
+        let extension = kind(&self.mime).map_or("bin", |(extension, _)| extension);
+        format!("{}.{extension}", self.hash)
+    }
+
+    /// The Markdown to paste in a leaf to show the file.
+    pub fn reference(&self) -> String {
+        let alt: String = self
+            .name
+            .chars()
+            .filter(|c| !matches!(c, '[' | ']' | '\\'))
+            .collect();
+
+        format!("![{alt}](media:{})", self.file_name())
+    }
+}
+
+/// The types that can be attached, as listed in the `accept` attribute of a file input.
+pub fn accepted_types() -> String {
+    TYPES
+        .iter()
+        .map(|(mime, _, _)| *mime)
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn kind(mime: &str) -> Option<(&'static str, Kind)> {
+    TYPES
+        .iter()
+        .find(|(known, _, _)| *known == mime)
+        .map(|&(_, extension, kind)| (extension, kind))
+}
+
+/// The hash and kind of a file served as `<hash>.<extension>`.
+pub fn parse_file_name(file_name: &str) -> Option<(&str, Kind)> {
+    let (hash, extension) = file_name.split_once('.')?;
+    let is_hash = hash.len() == 64
+        && hash
+            .bytes()
+            .all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b));
+    let (_, _, kind) = TYPES.iter().find(|(_, known, _)| *known == extension)?;
+
+    is_hash.then_some((hash, *kind))
+}
+
+/// Where a `media:` reference from a leaf is served, and what it holds. Other URLs are `None`.
+pub fn source(url: &str) -> Option<(String, Kind)> {
+    let file_name = url.strip_prefix("media:")?;
+    let (_, kind) = parse_file_name(file_name)?;
+
+    Some((format!("/media/{file_name}"), kind))
+}
+
+/// Where the content hashed as `hash` is stored under `dir`, spread over subdirectories named
+/// after the first two characters of the hash.
+pub fn path(dir: &Path, hash: &str) -> PathBuf {
+    dir.join(&hash[..2]).join(hash)
+}
+
+cfg_if::cfg_if! { if #[cfg(feature = "media")] {
+    use sha2::{Digest, Sha256};
+    use sqlx::SqlitePool;
+    use std::{
+        fs,
+        io::{self, Write},
+    };
+    use tempfile::NamedTempFile;
+
+    use crate::auth::AccessError;
+
+    impl Media {
+        /// Checks an upload and hashes its content.
+        pub fn new(name: &str, mime: &str, content: &[u8]) -> Result<Self, MediaError> {
+            if kind(mime).is_none() {
+                return Err(MediaError::Unsupported(mime.to_string()));
+            }
+            if content.is_empty() {
+                return Err(MediaError::Empty);
+            }
+            if content.len() > MAX_SIZE {
+                return Err(MediaError::TooLarge);
+            }
+
+            Ok(Self {
+                hash: format!("{:x}", Sha256::digest(content)),
+                mime: mime.to_string(),
+                name: name.to_string(),
+                size: content.len() as u32,
+            })
+        }
+
+        /// Writes `content` under `dir`, unless the same content is already stored there.
+        pub fn store(&self, content: &[u8], dir: &Path) -> io::Result<PathBuf> {
+            let path = path(dir, &self.hash);
+            if path.exists() {
+                return Ok(path);
+            }
+
+            let parent = path.parent().unwrap_or(dir);
+            fs::create_dir_all(parent)?;
+            // Written aside first, so a file is never served half written.
+            let mut file = NamedTempFile::new_in(parent)?;
+            file.write_all(content)?;
+
+            match file.persist(&path) {
+                Ok(_) => Ok(path),
+                // Stored by another upload of the same content in the meantime.
+                Err(_) if path.exists() => Ok(path),
+                Err(e) => Err(e.error),
+            }
+        }
+
+        /// Records that `user_id` uploaded the file, keeping the first name it was uploaded with.
+        pub async fn insert(&self, user_id: i64, pool: &SqlitePool) -> Result<(), sqlx::Error> {
+            sqlx::query("INSERT OR IGNORE INTO media (user_id, hash, mime, name, size) VALUES (?, ?, ?, ?, ?)")
+                .bind(user_id)
+                .bind(&self.hash)
+                .bind(&self.mime)
+                .bind(&self.name)
+                .bind(self.size)
+                .execute(pool)
+                .await
+                .map(|_| ())
+        }
+
+        /// The file hashed as `hash`, if `user_id` uploaded it. Files of other users are not found
+        /// rather than forbidden, so their existence isn't given away.
+        pub async fn get(hash: &str, user_id: i64, pool: &SqlitePool) -> Result<Self, AccessError> {
+            let (hash, mime, name, size) = sqlx::query_as::<_, (String, String, String, u32)>(
+                "SELECT hash, mime, name, size FROM media WHERE hash = ? AND user_id = ?",
+            )
+            .bind(hash)
+            .bind(user_id)
+            .fetch_one(pool)
+            .await?;
+
+            Ok(Self {
+                hash,
+                mime,
+                name,
+                size,
+            })
+        }
+    }
+}}
@@ -0,0 +1,242 @@
+//! Fits the FSRS weights to a user's review history.
+//!
+//! Each leaf's reviews are replayed through the FSRS-4.5 memory model, and the weights are tuned by
+//! gradient descent (Adam, with finite-difference gradients) to minimize the log loss between the
+//! predicted retrievability and whether each review was recalled. Reviews on the same day as the
+//! previous one are skipped, as FSRS-4.5 has no short-term model.
+
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+use fsrs::Parameters;
+
+use crate::{review_log::ReviewLog, Direction, Rating};
+
+/// Histories with fewer reviews keep the default weights.
+pub const MIN_REVIEWS: usize = 400;
+
+const DECAY: f64 = -0.5;
+const FACTOR: f64 = 19.0 / 81.0;
+
+const EPOCHS: usize = 200;
+const LEARNING_RATE: f64 = 0.04;
+const STEP: f64 = 1e-4;
+
+const BOUNDS: [(f64, f64); 17] = [
+    (0.1, 100.0),
+    (0.1, 100.0),
+    (0.1, 100.0),
+    (0.1, 100.0),
+    (1.0, 10.0),
+    (0.1, 5.0),
+    (0.1, 5.0),
+    (0.0, 0.5),
+    (0.0, 3.0),
+    (0.1, 0.8),
+    (0.01, 2.5),
+    (0.5, 5.0),
+    (0.01, 0.2),
+    (0.01, 0.9),
+    (0.01, 2.0),
+    (0.0, 1.0),
+    (1.0, 4.0),
+];
+
+/// One review: the days elapsed since the previous one and the rating given.
+type Review = (f64, Rating);
+
+/// Returns the weights that best fit `logs`, or `None` when there are too few reviews to learn
+/// from.
+pub fn optimize(logs: &[ReviewLog]) -> Option<[f64; 17]> {
+    let histories = histories(logs);
+    if review_count(&histories) < MIN_REVIEWS {
+        return None;
+    }
+
+    let mut w = Parameters::default().w;
+    let mut m = [0.0; 17];
+    let mut v = [0.0; 17];
+    let (beta1, beta2, epsilon) = (0.9, 0.999, 1e-8);
+
+    for epoch in 1..=EPOCHS {
+        let gradient = gradient(&w, &histories);
+
+        for i in 0..17 {
+            m[i] = beta1 * m[i] + (1.0 - beta1) * gradient[i];
+            v[i] = beta2 * v[i] + (1.0 - beta2) * gradient[i] * gradient[i];
+            let m_hat = m[i] / (1.0 - beta1.powi(epoch as i32));
+            let v_hat = v[i] / (1.0 - beta2.powi(epoch as i32));
+
+            let (min, max) = BOUNDS[i];
+            w[i] = (w[i] - LEARNING_RATE * m_hat / (v_hat.sqrt() + epsilon)).clamp(min, max);
+        }
+    }
+
+    Some(w)
+}
+
+/// How many of `logs` the weights are fitted to: the first review of each leaf and the ones on the
+/// same day as the previous review are left out.
+pub fn fitted_reviews(logs: &[ReviewLog]) -> usize {
+    review_count(&histories(logs))
+}
+
+/// The mean log loss of `w` over `logs`, or `None` when nothing can be predicted.
+pub fn loss(w: &[f64; 17], logs: &[ReviewLog]) -> Option<f64> {
+    let histories = histories(logs);
+    if histories.is_empty() {
+        return None;
+    }
+
+    Some(mean_loss(w, &histories))
+}
+
+/// Groups the reviews by leaf and direction, keeping only the ones reviewed on at least two
+/// different days.
+fn histories(logs: &[ReviewLog]) -> Vec<Vec<Review>> {
+    let mut leaves: BTreeMap<(u32, Direction), Vec<&ReviewLog>> = BTreeMap::new();
+    for log in logs {
+        leaves
+            .entry((log.leaf_id, log.direction))
+            .or_default()
+            .push(log);
+    }
+
+    leaves
+        .into_values()
+        .filter_map(|mut logs| {
+            logs.sort_by_key(|log| log.reviewed_at);
+
+            let mut history: Vec<Review> = Vec::new();
+            let mut last: Option<DateTime<Utc>> = None;
+            for log in logs {
+                let elapsed = last.map_or(0, |last| (log.reviewed_at - last).num_days());
+                if last.is_none() || elapsed > 0 {
+                    history.push((elapsed as f64, log.rating));
+                    last = Some(log.reviewed_at);
+                }
+            }
+
+            (history.len() > 1).then_some(history)
+        })
+        .collect()
+}
+
+fn review_count(histories: &[Vec<Review>]) -> usize {
+    histories.iter().map(|history| history.len() - 1).sum()
+}
+
+fn gradient(w: &[f64; 17], histories: &[Vec<Review>]) -> [f64; 17] {
+    let mut gradient = [0.0; 17];
+    for (i, slope) in gradient.iter_mut().enumerate() {
+        let mut above = *w;
+        let mut below = *w;
+        above[i] += STEP;
+        below[i] -= STEP;
+
+        *slope = (mean_loss(&above, histories) - mean_loss(&below, histories)) / (2.0 * STEP);
+    }
+
+    gradient
+}
+
+fn mean_loss(w: &[f64; 17], histories: &[Vec<Review>]) -> f64 {
+    let mut total = 0.0;
+    let mut count = 0;
+
+    for history in histories {
+        let (_, first) = history[0];
+        let mut stability = w[first as usize - 1];
+        let mut difficulty = initial_difficulty(w, first);
+
+        for &(elapsed, rating) in &history[1..] {
+            let retrievability = retrievability(elapsed, stability).clamp(1e-4, 1.0 - 1e-4);
+            total -= if rating == Rating::Again {
+                (1.0 - retrievability).ln()
+            } else {
+                retrievability.ln()
+            };
+            count += 1;
+
+            stability = next_stability(w, stability, difficulty, retrievability, rating);
+            difficulty = next_difficulty(w, difficulty, rating);
+        }
+    }
+
+    total / count as f64
+}
+
+fn retrievability(elapsed: f64, stability: f64) -> f64 {
+    (1.0 + FACTOR * elapsed / stability).powf(DECAY)
+}
+
+fn initial_difficulty(w: &[f64; 17], rating: Rating) -> f64 {
+    (w[4] - w[5] * (rating as i32 as f64 - 3.0)).clamp(1.0, 10.0)
+}
+
+fn next_difficulty(w: &[f64; 17], difficulty: f64, rating: Rating) -> f64 {
+    let next = difficulty - w[6] * (rating as i32 as f64 - 3.0);
+    let reverted = w[7] * initial_difficulty(w, Rating::Good) + (1.0 - w[7]) * next;
+
+    reverted.clamp(1.0, 10.0)
+}
+
+fn next_stability(
+    w: &[f64; 17],
+    stability: f64,
+    difficulty: f64,
+    retrievability: f64,
+    rating: Rating,
+) -> f64 {
+    let next = match rating {
+        Rating::Again => {
+            w[11]
+                * difficulty.powf(-w[12])
+                * ((stability + 1.0).powf(w[13]) - 1.0)
+                * (w[14] * (1.0 - retrievability)).exp()
+        }
+        _ => {
+            let hard_penalty = if rating == Rating::Hard { w[15] } else { 1.0 };
+            let easy_bonus = if rating == Rating::Easy { w[16] } else { 1.0 };
+
+            stability
+                * (w[8].exp()
+                    * (11.0 - difficulty)
+                    * stability.powf(-w[9])
+                    * ((w[10] * (1.0 - retrievability)).exp() - 1.0)
+                    * hard_penalty
+                    * easy_bonus
+                    + 1.0)
+        }
+    };
+
+    next.clamp(0.01, 36500.0)
+}
+
+cfg_if::cfg_if! { if #[cfg(feature = "auth")] {
+    use sqlx::SqlitePool;
+
+    /// The weights fitted to `user_id`'s reviews, if they were ever optimized.
+    pub async fn weights(user_id: i64, pool: &SqlitePool) -> Result<Option<[f64; 17]>, sqlx::Error> {
+        Ok(sqlx::query_scalar::<_, sqlx::types::Json<[f64; 17]>>("SELECT weights FROM user_parameters WHERE user_id = ?")
+            .bind(user_id)
+            .fetch_optional(pool)
+            .await?
+            .map(|weights| weights.0))
+    }
+
+    /// Stores `weights` as `user_id`'s own, fitted to `review_count` reviews.
+    pub async fn store_weights(user_id: i64, weights: &[f64; 17], review_count: usize, now: DateTime<Utc>, pool: &SqlitePool) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "INSERT INTO user_parameters (user_id, weights, review_count, optimized_at) VALUES (?, ?, ?, ?)
+                ON CONFLICT (user_id) DO UPDATE SET weights = excluded.weights, review_count = excluded.review_count, optimized_at = excluded.optimized_at",
+        )
+        .bind(user_id)
+        .bind(sqlx::types::Json(weights))
+        .bind(review_count as i64)
+        .bind(now)
+        .execute(pool)
+        .await
+        .map(|_| ())
+    }
+}}
@@ -0,0 +1,179 @@
+//! The leaves due for review. Leaves already learned come first, the most overdue first, followed
+//! by new leaves in the order they were grown. Leaves in review are due for the whole study day,
+//! while leaves being (re)learned wait for their learning step. Each direction of a bidirectional
+//! leaf is queued and counted on its own.
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QueueLimits {
+    /// How many new leaves the queue holds at most.
+    pub new: u32,
+    /// How many leaves already learned the queue holds at most.
+    pub review: u32,
+}
+
+impl Default for QueueLimits {
+    fn default() -> Self {
+        Self {
+            new: 20,
+            review: 200,
+        }
+    }
+}
+
+/// How many leaves of a branch or a stem are due today, new, and in all. Due leaves are counted
+/// regardless of the daily limits, and so are both directions of bidirectional leaves.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LeafCounts {
+    pub due: u32,
+    pub new: u32,
+    pub total: u32,
+}
+
+/// Which leaves of a user a queue is drawn from.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Scope {
+    #[default]
+    All,
+    Branch(u32),
+    Stem(u32),
+}
+
+cfg_if::cfg_if! { if #[cfg(feature = "auth")] {
+    use std::collections::HashMap;
+
+    use chrono::{DateTime, Utc};
+    use sqlx::SqlitePool;
+
+    use crate::{settings::{DailyCounts, StudySettings}, Leaf, SqlLeaf};
+
+    #[derive(Debug, Clone, Copy)]
+    pub struct DueQuery {
+        pub user_id: i64,
+        pub scope: Scope,
+        pub now: DateTime<Utc>,
+        /// Leaves in review are due until the end of the study day, not only until `now`.
+        pub day_end: DateTime<Utc>,
+        pub limits: QueueLimits,
+    }
+
+    /// A page of today's queue of `user_id` within `scope`, within what is left of their daily limits.
+    pub async fn study_queue(user_id: i64, scope: Scope, now: DateTime<Utc>, offset: u32, limit: u32, pool: &SqlitePool) -> Result<Vec<Leaf>, sqlx::Error> {
+        let settings = StudySettings::for_user(user_id, pool).await?;
+        let done = DailyCounts::since(user_id, settings.day_start(now), pool).await?;
+
+        let query = DueQuery { user_id, scope, now, day_end: settings.day_end(now), limits: settings.limits(done) };
+        query.leaves(offset, limit, pool).await
+    }
+
+    impl DueQuery {
+        /// A page of the due leaves, skipping the first `offset`.
+        pub async fn leaves(&self, offset: u32, limit: u32, pool: &SqlitePool) -> Result<Vec<Leaf>, sqlx::Error> {
+            let (branch_id, stem_id) = match self.scope {
+                Scope::All => (None, None),
+                Scope::Branch(id) => (Some(id), None),
+                Scope::Stem(id) => (None, Some(id)),
+            };
+
+            Ok(sqlx::query_as::<_, SqlLeaf>(
+                "SELECT id, stem_id, front, back, card, cloze, note, reverse_card, created_at, version, reversed FROM (
+                    SELECT * FROM (
+                        SELECT l.*, 0 AS queue FROM leaf_sides l
+                            INNER JOIN stems s
+                                ON s.id = l.stem_id
+                                AND ($8 IS NULL OR s.id = $8)
+                            INNER JOIN branches b
+                                ON b.id = s.branch_id
+                                AND b.user_id = $1
+                                AND ($7 IS NULL OR b.id = $7)
+                            WHERE (l.state = 'Review' AND l.due < datetime($3))
+                                OR (l.state IN ('Learning', 'Relearning') AND l.due <= datetime($2))
+                            ORDER BY l.due, l.id, l.reversed
+                            LIMIT $4
+                    )
+                    UNION ALL
+                    SELECT * FROM (
+                        SELECT l.*, 1 AS queue FROM leaf_sides l
+                            INNER JOIN stems s
+                                ON s.id = l.stem_id
+                                AND ($8 IS NULL OR s.id = $8)
+                            INNER JOIN branches b
+                                ON b.id = s.branch_id
+                                AND b.user_id = $1
+                                AND ($7 IS NULL OR b.id = $7)
+                            WHERE l.state = 'New'
+                            ORDER BY l.id, l.reversed
+                            LIMIT $5
+                    )
+                )
+                ORDER BY queue, CASE queue WHEN 0 THEN due END, id, reversed
+                LIMIT $6 OFFSET $9",
+            )
+            .bind(self.user_id)
+            .bind(self.now)
+            .bind(self.day_end)
+            .bind(self.limits.review)
+            .bind(self.limits.new)
+            .bind(limit)
+            .bind(branch_id)
+            .bind(stem_id)
+            .bind(offset)
+            .fetch_all(pool)
+            .await?
+            .iter()
+            .map(SqlLeaf::into_leaf)
+            .collect())
+        }
+    }
+
+    impl LeafCounts {
+        /// The counts of every branch of `user_id`, in a single query.
+        pub async fn per_branch(user_id: i64, now: DateTime<Utc>, day_end: DateTime<Utc>, pool: &SqlitePool) -> Result<HashMap<u32, Self>, sqlx::Error> {
+            Ok(sqlx::query_as::<_, (u32, u32, u32, u32)>(
+                "SELECT b.id,
+                        COALESCE(SUM((l.state = 'Review' AND l.due < datetime($3)) OR (l.state IN ('Learning', 'Relearning') AND l.due <= datetime($2))), 0),
+                        COALESCE(SUM(l.state = 'New'), 0),
+                        COUNT(DISTINCT l.id)
+                    FROM branches b
+                    LEFT JOIN stems s
+                        ON s.branch_id = b.id
+                    LEFT JOIN leaf_sides l
+                        ON l.stem_id = s.id
+                    WHERE b.user_id = $1
+                    GROUP BY b.id",
+            )
+            .bind(user_id)
+            .bind(now)
+            .bind(day_end)
+            .fetch_all(pool)
+            .await?
+            .into_iter()
+            .map(|(id, due, new, total)| (id, Self { due, new, total }))
+            .collect())
+        }
+
+        /// The counts of every stem of `branch_id`, in a single query.
+        pub async fn per_stem(branch_id: u32, now: DateTime<Utc>, day_end: DateTime<Utc>, pool: &SqlitePool) -> Result<HashMap<u32, Self>, sqlx::Error> {
+            Ok(sqlx::query_as::<_, (u32, u32, u32, u32)>(
+                "SELECT s.id,
+                        COALESCE(SUM((l.state = 'Review' AND l.due < datetime($3)) OR (l.state IN ('Learning', 'Relearning') AND l.due <= datetime($2))), 0),
+                        COALESCE(SUM(l.state = 'New'), 0),
+                        COUNT(DISTINCT l.id)
+                    FROM stems s
+                    LEFT JOIN leaf_sides l
+                        ON l.stem_id = s.id
+                    WHERE s.branch_id = $1
+                    GROUP BY s.id",
+            )
+            .bind(branch_id)
+            .bind(now)
+            .bind(day_end)
+            .fetch_all(pool)
+            .await?
+            .into_iter()
+            .map(|(id, due, new, total)| (id, Self { due, new, total }))
+            .collect())
+        }
+    }
+}}
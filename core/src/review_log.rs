@@ -0,0 +1,268 @@
+//! The history of every rating given to a leaf, one row of `review_logs` per review.
+
+use chrono::{DateTime, Utc};
+use fsrs::State;
+use serde::{Deserialize, Serialize};
+
+use crate::{Card, Direction, Rating};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReviewLog {
+    pub id: u32,
+    pub leaf_id: u32,
+    /// The direction of the leaf that was reviewed.
+    #[serde(default)]
+    pub direction: Direction,
+    pub user_id: i64,
+    pub rating: Rating,
+    /// The state of the card before this review.
+    pub state: State,
+    pub elapsed_days: i64,
+    pub scheduled_days: i64,
+    /// The card as it was before this review, so the review can be undone.
+    pub previous_card: Card,
+    pub reviewed_at: DateTime<Utc>,
+}
+
+impl ReviewLog {
+    /// Describes the review that turned `previous_card` into `card`.
+    pub fn new(
+        leaf_id: u32,
+        user_id: i64,
+        rating: Rating,
+        previous_card: Card,
+        card: &Card,
+        now: DateTime<Utc>,
+    ) -> Self {
+        Self {
+            id: 0,
+            leaf_id,
+            direction: Direction::Forward,
+            user_id,
+            rating,
+            state: previous_card.state,
+            elapsed_days: card.elapsed_days,
+            scheduled_days: card.scheduled_days,
+            previous_card,
+            reviewed_at: now,
+        }
+    }
+}
+
+pub(crate) fn state(value: i64) -> Option<State> {
+    match value {
+        0 => Some(State::New),
+        1 => Some(State::Learning),
+        2 => Some(State::Review),
+        3 => Some(State::Relearning),
+        _ => None,
+    }
+}
+
+cfg_if::cfg_if! { if #[cfg(feature = "auth")] {
+    use sqlx::{FromRow, SqliteConnection, SqlitePool};
+
+    use crate::{deck_file::rating, Config, Leaf};
+
+    #[derive(Debug, thiserror::Error)]
+    pub enum ReviewError {
+        #[error("The leaf was reviewed somewhere else in the meantime")]
+        Conflict,
+        #[error(transparent)]
+        Database(#[from] sqlx::Error),
+    }
+
+    #[derive(FromRow)]
+    pub struct SqlReviewLog {
+        id: u32,
+        leaf_id: u32,
+        user_id: i64,
+        rating: u32,
+        state: i64,
+        elapsed_days: i64,
+        scheduled_days: i64,
+        previous_card: sqlx::types::Json<Card>,
+        reviewed_at: DateTime<Utc>,
+        reversed: bool,
+    }
+
+    impl SqlReviewLog {
+        pub fn into_review_log(&self) -> ReviewLog {
+            ReviewLog {
+                id: self.id,
+                leaf_id: self.leaf_id,
+                direction: Direction::from_reversed(self.reversed),
+                user_id: self.user_id,
+                rating: rating(self.rating).unwrap_or(Rating::Again),
+                state: state(self.state).unwrap_or(State::New),
+                elapsed_days: self.elapsed_days,
+                scheduled_days: self.scheduled_days,
+                previous_card: self.previous_card.0.clone(),
+                reviewed_at: self.reviewed_at,
+            }
+        }
+    }
+
+    impl ReviewLog {
+        pub async fn insert(&self, connection: &mut SqliteConnection) -> Result<i64, sqlx::Error> {
+            sqlx::query(
+                "INSERT INTO review_logs (leaf_id, user_id, rating, state, elapsed_days, scheduled_days, previous_card, reviewed_at, reversed)
+                    VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)",
+            )
+            .bind(self.leaf_id)
+            .bind(self.user_id)
+            .bind(self.rating as u32)
+            .bind(self.state as i64)
+            .bind(self.elapsed_days)
+            .bind(self.scheduled_days)
+            .bind(sqlx::types::Json(&self.previous_card))
+            .bind(self.reviewed_at)
+            .bind(self.direction.is_reverse())
+            .execute(connection)
+            .await
+            .map(|result| result.last_insert_rowid())
+        }
+
+        /// Every review of `user_id`, oldest first.
+        pub async fn for_user(user_id: i64, pool: &SqlitePool) -> Result<Vec<Self>, sqlx::Error> {
+            Ok(sqlx::query_as::<_, SqlReviewLog>("SELECT * FROM review_logs WHERE user_id = ? ORDER BY reviewed_at, id")
+                .bind(user_id)
+                .fetch_all(pool)
+                .await?
+                .iter()
+                .map(SqlReviewLog::into_review_log)
+                .collect())
+        }
+
+        /// The reviews of `user_id` since `since`, oldest first.
+        pub async fn since(user_id: i64, since: DateTime<Utc>, pool: &SqlitePool) -> Result<Vec<Self>, sqlx::Error> {
+            Ok(sqlx::query_as::<_, SqlReviewLog>("SELECT * FROM review_logs WHERE user_id = ? AND reviewed_at >= ? ORDER BY reviewed_at, id")
+                .bind(user_id)
+                .bind(since)
+                .fetch_all(pool)
+                .await?
+                .iter()
+                .map(SqlReviewLog::into_review_log)
+                .collect())
+        }
+
+        /// Every review of `leaf_id`, oldest first.
+        pub async fn for_leaf(leaf_id: u32, pool: &SqlitePool) -> Result<Vec<Self>, sqlx::Error> {
+            Ok(sqlx::query_as::<_, SqlReviewLog>("SELECT * FROM review_logs WHERE leaf_id = ? ORDER BY reviewed_at, id")
+                .bind(leaf_id)
+                .fetch_all(pool)
+                .await?
+                .iter()
+                .map(SqlReviewLog::into_review_log)
+                .collect())
+        }
+
+        /// The latest review of `user_id`, if any.
+        pub async fn last_for_user(user_id: i64, pool: &SqlitePool) -> Result<Option<Self>, sqlx::Error> {
+            Ok(sqlx::query_as::<_, SqlReviewLog>("SELECT * FROM review_logs WHERE user_id = ? ORDER BY reviewed_at DESC, id DESC LIMIT 1")
+                .bind(user_id)
+                .fetch_optional(pool)
+                .await?
+                .map(|log| log.into_review_log()))
+        }
+    }
+
+    impl ReviewLog {
+        /// Takes back the review of `user_id` logged as `id`, as long as the leaf was not reviewed in
+        /// the same direction since, nor made one-way after a review from back to front: the leaf gets
+        /// its previous card back and the log is deleted, in one transaction. Returns the undone review
+        /// and the version the leaf is at now, if it could be undone.
+        pub async fn undo(user_id: i64, id: u32, pool: &SqlitePool) -> Result<Option<(Self, u32)>, sqlx::Error> {
+            let mut transaction = pool.begin().await?;
+
+            let Some(log) = sqlx::query_as::<_, SqlReviewLog>(
+                "SELECT * FROM review_logs l
+                    WHERE id = ? AND user_id = ? AND NOT EXISTS (
+                        SELECT 1 FROM review_logs later
+                            WHERE later.leaf_id = l.leaf_id AND later.reversed = l.reversed AND later.id > l.id
+                    )",
+            )
+            .bind(id)
+            .bind(user_id)
+            .fetch_optional(&mut *transaction)
+            .await?
+            .map(|log| log.into_review_log())
+            else {
+                return Ok(None);
+            };
+
+            let Some(version) = log.restore(&mut transaction).await? else {
+                return Ok(None);
+            };
+            transaction.commit().await?;
+
+            Ok(Some((log, version)))
+        }
+
+        /// Gives the leaf back the card it had before this review and deletes the log. Returns the new
+        /// version of the leaf, or `None` for a review from back to front of a leaf that has been made
+        /// one-way since, which is left as it is.
+        async fn restore(&self, connection: &mut SqliteConnection) -> Result<Option<u32>, sqlx::Error> {
+            let restore = match self.direction {
+                Direction::Forward => "UPDATE leaves SET card = ?, version = version + 1 WHERE id = ? RETURNING version",
+                Direction::Reverse => "UPDATE leaves SET reverse_card = ?, version = version + 1 WHERE id = ? AND reverse_card IS NOT NULL RETURNING version",
+            };
+            let Some(version) = sqlx::query_scalar(restore)
+                .bind(sqlx::types::Json(&self.previous_card))
+                .bind(self.leaf_id)
+                .fetch_optional(&mut *connection)
+                .await?
+            else {
+                return Ok(None);
+            };
+            sqlx::query("DELETE FROM review_logs WHERE id = ?")
+                .bind(self.id)
+                .execute(&mut *connection)
+                .await?;
+
+            Ok(Some(version))
+        }
+    }
+
+    impl Leaf {
+        /// Reviews the leaf in the direction it is asked in, then stores its new card and the review log
+        /// in one transaction.
+        ///
+        /// The card is only stored if the leaf was not reviewed or undone since it was loaded, as told by
+        /// its version; otherwise nothing is written and [`ReviewError::Conflict`] is returned.
+        pub async fn record_review(&mut self, user_id: i64, config: &Config, rating: Rating, now: DateTime<Utc>, pool: &SqlitePool) -> Result<ReviewLog, ReviewError> {
+            let previous_card = self.asked_card().clone();
+            self.review(config, rating, now);
+
+            let mut log = ReviewLog {
+                direction: self.direction,
+                ..ReviewLog::new(self.id, user_id, rating, previous_card, self.asked_card(), now)
+            };
+
+            let mut transaction = pool.begin().await?;
+
+            let update = match self.direction {
+                Direction::Forward => "UPDATE leaves SET card = ?, version = version + 1 WHERE id = ? AND version = ?",
+                Direction::Reverse => "UPDATE leaves SET reverse_card = ?, version = version + 1 WHERE id = ? AND version = ?",
+            };
+            let updated = sqlx::query(update)
+                .bind(sqlx::types::Json(self.asked_card()))
+                .bind(self.id)
+                .bind(self.version)
+                .execute(&mut *transaction)
+                .await?
+                .rows_affected();
+            if updated == 0 {
+                *self.asked_card_mut() = log.previous_card;
+                return Err(ReviewError::Conflict);
+            }
+
+            log.id = log.insert(&mut transaction).await? as u32;
+
+            transaction.commit().await?;
+            self.version += 1;
+
+            Ok(log)
+        }
+    }
+}}
@@ -0,0 +1,177 @@
+//! How much a user studies each day, and when their study day starts.
+
+use chrono::{DateTime, Duration, NaiveDate, TimeZone, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::queue::QueueLimits;
+
+#[derive(Debug, Error)]
+pub enum SettingsError {
+    #[error("Daily limits must be whole numbers")]
+    Limit,
+    #[error("The day must start at an hour between 0 and 23")]
+    DayStartsAt,
+    #[error("Unknown timezone \"{0}\"")]
+    Timezone(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StudySettings {
+    pub new_per_day: u32,
+    pub reviews_per_day: u32,
+    /// The hour at which a new study day starts, so late-night reviews count towards the day before.
+    pub day_starts_at: u32,
+    /// An IANA timezone name, such as `Europe/Paris`.
+    pub timezone: String,
+}
+
+impl Default for StudySettings {
+    fn default() -> Self {
+        Self {
+            new_per_day: 20,
+            reviews_per_day: 200,
+            day_starts_at: 4,
+            timezone: "UTC".to_string(),
+        }
+    }
+}
+
+/// How many new leaves and reviews were studied since the study day started.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DailyCounts {
+    pub new: u32,
+    pub review: u32,
+}
+
+impl StudySettings {
+    /// Reads settings as typed in a form.
+    pub fn parse(
+        new_per_day: &str,
+        reviews_per_day: &str,
+        day_starts_at: &str,
+        timezone: &str,
+    ) -> Result<Self, SettingsError> {
+        let limit = |value: &str| {
+            value
+                .trim()
+                .parse::<u32>()
+                .map_err(|_| SettingsError::Limit)
+        };
+
+        let day_starts_at = day_starts_at
+            .trim()
+            .parse::<u32>()
+            .ok()
+            .filter(|hour| *hour < 24)
+            .ok_or(SettingsError::DayStartsAt)?;
+        let timezone = timezone.trim();
+        timezone
+            .parse::<Tz>()
+            .map_err(|_| SettingsError::Timezone(timezone.to_string()))?;
+
+        Ok(Self {
+            new_per_day: limit(new_per_day)?,
+            reviews_per_day: limit(reviews_per_day)?,
+            day_starts_at,
+            timezone: timezone.to_string(),
+        })
+    }
+
+    fn tz(&self) -> Tz {
+        self.timezone.parse().unwrap_or(Tz::UTC)
+    }
+
+    /// The local date of the study day `now` falls in.
+    pub fn study_date(&self, now: DateTime<Utc>) -> NaiveDate {
+        (now.with_timezone(&self.tz()) - Duration::hours(self.day_starts_at.into())).date_naive()
+    }
+
+    /// When the study day of `date` starts.
+    pub fn start_of(&self, date: NaiveDate) -> DateTime<Utc> {
+        let start = date
+            .and_hms_opt(self.day_starts_at, 0, 0)
+            .unwrap_or_default();
+
+        // A start skipped by a daylight saving change falls back to an hour later.
+        self.tz()
+            .from_local_datetime(&start)
+            .earliest()
+            .or_else(|| {
+                self.tz()
+                    .from_local_datetime(&(start + Duration::hours(1)))
+                    .earliest()
+            })
+            .map_or_else(|| start.and_utc(), |start| start.with_timezone(&Utc))
+    }
+
+    /// When the study day `now` falls in started.
+    pub fn day_start(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        self.start_of(self.study_date(now))
+    }
+
+    /// When the study day `now` falls in ends.
+    pub fn day_end(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        self.start_of(self.study_date(now) + Duration::days(1))
+    }
+
+    /// What is left of today's limits once `done` have been studied.
+    pub fn limits(&self, done: DailyCounts) -> QueueLimits {
+        QueueLimits {
+            new: self.new_per_day.saturating_sub(done.new),
+            review: self.reviews_per_day.saturating_sub(done.review),
+        }
+    }
+}
+
+cfg_if::cfg_if! { if #[cfg(feature = "auth")] {
+    use sqlx::SqlitePool;
+
+    impl StudySettings {
+        pub async fn for_user(user_id: i64, pool: &SqlitePool) -> Result<Self, sqlx::Error> {
+            Ok(sqlx::query_as::<_, (u32, u32, u32, String)>("SELECT new_per_day, reviews_per_day, day_starts_at, timezone FROM user_settings WHERE user_id = ?")
+                .bind(user_id)
+                .fetch_optional(pool)
+                .await?
+                .map_or_else(StudySettings::default, |(new_per_day, reviews_per_day, day_starts_at, timezone)| StudySettings { new_per_day, reviews_per_day, day_starts_at, timezone }))
+        }
+
+        pub async fn save(&self, user_id: i64, pool: &SqlitePool) -> Result<(), sqlx::Error> {
+            sqlx::query(
+                "INSERT INTO user_settings (user_id, new_per_day, reviews_per_day, day_starts_at, timezone) VALUES (?, ?, ?, ?, ?)
+                    ON CONFLICT (user_id) DO UPDATE SET new_per_day = excluded.new_per_day, reviews_per_day = excluded.reviews_per_day, day_starts_at = excluded.day_starts_at, timezone = excluded.timezone",
+            )
+            .bind(user_id)
+            .bind(self.new_per_day)
+            .bind(self.reviews_per_day)
+            .bind(self.day_starts_at)
+            .bind(&self.timezone)
+            .execute(pool)
+            .await
+            .map(|_| ())
+        }
+    }
+
+    impl DailyCounts {
+        /// Counts the new leaves and the reviews `user_id` studied since `since`, from the review log.
+        /// Leaves repeated while (re)learning count towards neither, and each direction of a
+        /// bidirectional leaf counts on its own, as it is queued.
+        pub async fn since(user_id: i64, since: DateTime<Utc>, pool: &SqlitePool) -> Result<Self, sqlx::Error> {
+            let (new, review) = sqlx::query_as::<_, (u32, u32)>(
+                "SELECT
+                    (SELECT COUNT(*) FROM (
+                        SELECT DISTINCT leaf_id, reversed FROM review_logs
+                            WHERE user_id = $1 AND reviewed_at >= $2 AND state = 0
+                    )),
+                    (SELECT COUNT(*) FROM review_logs WHERE user_id = $1 AND reviewed_at >= $2 AND state = 2)",
+            )
+            .bind(user_id)
+            .bind(since)
+            .fetch_one(pool)
+            .await?;
+
+            Ok(Self { new, review })
+        }
+    }
+}}
@@ -0,0 +1,229 @@
+//! Review statistics, as series ready to be charted. Days are study days, as set by the user's
+//! [`StudySettings`].
+
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use fsrs::State;
+use serde::{Deserialize, Serialize};
+
+use crate::{review_log::ReviewLog, settings::StudySettings, Card, Rating};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DayCount {
+    pub date: NaiveDate,
+    pub count: u32,
+}
+
+/// How many leaves are in each state, and how well the ones already studied are remembered.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct CardStats {
+    pub new: u32,
+    pub learning: u32,
+    pub review: u32,
+    pub relearning: u32,
+    /// In days, over the leaves that are not new.
+    pub average_stability: Option<f64>,
+}
+
+impl CardStats {
+    pub fn from_cards(cards: &[Card]) -> Self {
+        let mut stats = Self::default();
+        let mut stability = 0.0;
+
+        for card in cards {
+            match card.state {
+                State::New => stats.new += 1,
+                State::Learning => stats.learning += 1,
+                State::Review => stats.review += 1,
+                State::Relearning => stats.relearning += 1,
+            }
+            if card.state != State::New {
+                stability += card.stability;
+            }
+        }
+
+        let studied = stats.learning + stats.review + stats.relearning;
+        stats.average_stability = (studied > 0).then(|| stability / f64::from(studied));
+
+        stats
+    }
+}
+
+/// The number of reviews on each of the `days` study days up to `today`, oldest first.
+pub fn reviews_per_day(
+    logs: &[ReviewLog],
+    settings: &StudySettings,
+    today: NaiveDate,
+    days: u32,
+) -> Vec<DayCount> {
+    let first = today - Duration::days(i64::from(days) - 1);
+    let mut series = series(first, days);
+
+    for log in logs {
+        let date = settings.study_date(log.reviewed_at);
+        if let Some(day) = series.iter_mut().find(|day| day.date == date) {
+            day.count += 1;
+        }
+    }
+
+    series
+}
+
+/// The share of reviews of leaves already learned that were recalled, or `None` without any.
+/// Leaves being (re)learned are left out, as they are expected to be forgotten.
+pub fn true_retention(logs: &[ReviewLog]) -> Option<f64> {
+    let reviews = logs.iter().filter(|log| log.state == State::Review);
+    let (total, recalled) = reviews.fold((0, 0), |(total, recalled), log| {
+        (total + 1, recalled + u32::from(log.rating != Rating::Again))
+    });
+
+    (total > 0).then(|| f64::from(recalled) / f64::from(total))
+}
+
+/// The number of leaves due on each of the `days` study days from `today`. Overdue leaves count
+/// towards today, and new leaves are left out.
+pub fn forecast(
+    cards: &[Card],
+    settings: &StudySettings,
+    today: NaiveDate,
+    days: u32,
+) -> Vec<DayCount> {
+    let mut series = series(today, days);
+
+    for card in cards.iter().filter(|card| card.state != State::New) {
+        let date = settings.study_date(card.due).max(today);
+        if let Some(day) = series.iter_mut().find(|day| day.date == date) {
+            day.count += 1;
+        }
+    }
+
+    series
+}
+
+/// The longest run of consecutive study days with reviews, and the run going on now.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Streaks {
+    pub current: u32,
+    pub longest: u32,
+}
+
+impl Streaks {
+    /// The current streak is not broken until today ends without a review.
+    pub fn from_days(days: &BTreeMap<NaiveDate, u32>, today: NaiveDate) -> Self {
+        let mut longest = 0;
+        let mut run = 0;
+        let mut previous: Option<NaiveDate> = None;
+        for &date in days.keys() {
+            run = match previous {
+                Some(previous) if previous + Duration::days(1) == date => run + 1,
+                _ => 1,
+            };
+            longest = longest.max(run);
+            previous = Some(date);
+        }
+
+        let mut current = 0;
+        let mut date = if days.contains_key(&today) {
+            today
+        } else {
+            today - Duration::days(1)
+        };
+        while days.contains_key(&date) {
+            current += 1;
+            date -= Duration::days(1);
+        }
+
+        Self { current, longest }
+    }
+}
+
+/// Adds up review counts, such as the ones of `review_counts`, per study day.
+pub fn study_days(
+    counts: &[(DateTime<Utc>, u32)],
+    settings: &StudySettings,
+) -> BTreeMap<NaiveDate, u32> {
+    let mut days = BTreeMap::new();
+    for &(time, count) in counts {
+        *days.entry(settings.study_date(time)).or_default() += count;
+    }
+
+    days
+}
+
+/// The number of reviews on each of the last `count` study days up to `today`, oldest first.
+pub fn heatmap(days: &BTreeMap<NaiveDate, u32>, today: NaiveDate, count: u32) -> Vec<DayCount> {
+    let first = today - Duration::days(i64::from(count) - 1);
+
+    series(first, count)
+        .into_iter()
+        .map(|day| DayCount {
+            count: days.get(&day.date).copied().unwrap_or(0),
+            ..day
+        })
+        .collect()
+}
+
+fn series(first: NaiveDate, days: u32) -> Vec<DayCount> {
+    (0..days)
+        .map(|day| DayCount {
+            date: first + Duration::days(day.into()),
+            count: 0,
+        })
+        .collect()
+}
+
+cfg_if::cfg_if! { if #[cfg(feature = "auth")] {
+    use sqlx::SqlitePool;
+
+    /// The reviews of `user_id` counted per quarter of an hour, the finest step of any timezone
+    /// offset, so they can be split into study days without loading every review.
+    pub async fn review_counts(user_id: i64, pool: &SqlitePool) -> Result<Vec<(DateTime<Utc>, u32)>, sqlx::Error> {
+        Ok(sqlx::query_as::<_, (String, u32)>(
+            "SELECT strftime('%Y-%m-%dT%H:', reviewed_at) || printf('%02d:00Z', CAST(strftime('%M', reviewed_at) AS INTEGER) / 15 * 15) AS quarter, COUNT(*)
+                FROM review_logs
+                WHERE user_id = ?
+                GROUP BY quarter
+                ORDER BY quarter",
+        )
+        .bind(user_id)
+        .fetch_all(pool)
+        .await?
+        .into_iter()
+        .filter_map(|(quarter, count)| Some((DateTime::parse_from_rfc3339(&quarter).ok()?.with_timezone(&Utc), count)))
+        .collect())
+    }
+
+    /// How many leaves `user_id` owns.
+    pub async fn leaf_count(user_id: i64, pool: &SqlitePool) -> Result<u32, sqlx::Error> {
+        sqlx::query_scalar::<_, u32>(
+            "SELECT COUNT(*) FROM leaves l
+                INNER JOIN stems s
+                    ON s.id = l.stem_id
+                INNER JOIN branches b
+                    ON b.id = s.branch_id
+                WHERE b.user_id = ?",
+        )
+        .bind(user_id)
+        .fetch_one(pool)
+        .await
+    }
+
+    /// The cards of every leaf of `user_id`, both of them for bidirectional leaves.
+    pub async fn cards(user_id: i64, pool: &SqlitePool) -> Result<Vec<Card>, sqlx::Error> {
+        Ok(sqlx::query_scalar::<_, sqlx::types::Json<Card>>(
+            "SELECT l.side_card FROM leaf_sides l
+                INNER JOIN stems s
+                    ON s.id = l.stem_id
+                INNER JOIN branches b
+                    ON b.id = s.branch_id
+                WHERE b.user_id = ?",
+        )
+        .bind(user_id)
+        .fetch_all(pool)
+        .await?
+        .into_iter()
+        .map(|card| card.0)
+        .collect())
+    }
+}}
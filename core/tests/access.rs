@@ -0,0 +1,66 @@
+mod common;
+
+use brainace_core::auth::{AccessError, User};
+use common::{pool, user};
+use sqlx::SqlitePool;
+
+/// Grows a branch with one stem and one leaf for `user`, returning their ids.
+async fn garden(user: &User, pool: &SqlitePool) -> (u32, u32, u32) {
+    let branch_id = common::branch(user.id, pool).await;
+    let stem_id = common::stem(branch_id, pool).await;
+    let leaf_id = common::leaf(stem_id, "Front", pool).await;
+
+    (branch_id, stem_id, leaf_id)
+}
+
+#[tokio::test]
+async fn owner_is_authorized() {
+    let pool = pool().await;
+    let alice = user("alice", &pool).await;
+    let (branch_id, stem_id, leaf_id) = garden(&alice, &pool).await;
+
+    assert!(alice.authorize_branch(branch_id, &pool).await.is_ok());
+    assert!(alice.authorize_stem(stem_id, &pool).await.is_ok());
+    assert!(alice.authorize_leaf(leaf_id, &pool).await.is_ok());
+}
+
+#[tokio::test]
+async fn foreign_ids_are_forbidden() {
+    let pool = pool().await;
+    let alice = user("alice", &pool).await;
+    let bob = user("bob", &pool).await;
+    let (branch_id, stem_id, leaf_id) = garden(&alice, &pool).await;
+    garden(&bob, &pool).await;
+
+    assert!(matches!(
+        bob.authorize_branch(branch_id, &pool).await,
+        Err(AccessError::Forbidden)
+    ));
+    assert!(matches!(
+        bob.authorize_stem(stem_id, &pool).await,
+        Err(AccessError::Forbidden)
+    ));
+    assert!(matches!(
+        bob.authorize_leaf(leaf_id, &pool).await,
+        Err(AccessError::Forbidden)
+    ));
+}
+
+#[tokio::test]
+async fn missing_ids_are_not_found() {
+    let pool = pool().await;
+    let alice = user("alice", &pool).await;
+
+    assert!(matches!(
+        alice.authorize_branch(42, &pool).await,
+        Err(AccessError::NotFound)
+    ));
+    assert!(matches!(
+        alice.authorize_stem(42, &pool).await,
+        Err(AccessError::NotFound)
+    ));
+    assert!(matches!(
+        alice.authorize_leaf(42, &pool).await,
+        Err(AccessError::NotFound)
+    ));
+}
@@ -0,0 +1,133 @@
+mod common;
+
+use std::io::{Cursor, Write};
+
+use brainace_core::{anki::read_apkg, deck_file::DeckFile};
+use chrono::Utc;
+use sqlx::{sqlite::SqliteConnectOptions, Connection, SqliteConnection};
+use zip::{write::FileOptions, ZipWriter};
+
+const DAY: i64 = 86_400;
+
+/// Packs a legacy (JSON decks) collection with one basic note, one reversed note and a review log.
+async fn package(crt: i64) -> Vec<u8> {
+    let file = tempfile::NamedTempFile::new().unwrap();
+    let options = SqliteConnectOptions::new()
+        .filename(file.path())
+        .create_if_missing(true);
+    let mut connection = SqliteConnection::connect_with(&options).await.unwrap();
+
+    for statement in [
+        "CREATE TABLE col (crt INTEGER NOT NULL, decks TEXT NOT NULL)",
+        "CREATE TABLE notes (id INTEGER PRIMARY KEY, flds TEXT NOT NULL)",
+        "CREATE TABLE cards (id INTEGER PRIMARY KEY, nid INTEGER, did INTEGER, ord INTEGER, type INTEGER, due INTEGER)",
+        "CREATE TABLE revlog (id INTEGER PRIMARY KEY, cid INTEGER, ease INTEGER, type INTEGER)",
+    ] {
+        sqlx::query(statement).execute(&mut connection).await.unwrap();
+    }
+
+    sqlx::query("INSERT INTO col VALUES (?, ?)")
+        .bind(crt)
+        .bind(r#"{"1": {"name": "Default"}, "2": {"name": "Languages::French"}}"#)
+        .execute(&mut connection)
+        .await
+        .unwrap();
+    sqlx::query("INSERT INTO notes VALUES (1, 'Front\x1fBack'), (2, 'chat\x1fcat')")
+        .execute(&mut connection)
+        .await
+        .unwrap();
+    sqlx::query(
+        "INSERT INTO cards VALUES (10, 1, 1, 0, 0, 1), (20, 2, 2, 0, 2, 30), (21, 2, 2, 1, 0, 2)",
+    )
+    .execute(&mut connection)
+    .await
+    .unwrap();
+    sqlx::query("INSERT INTO revlog VALUES (?, 20, 3, 0), (?, 20, 3, 1)")
+        .bind((crt + DAY) * 1000)
+        .bind((crt + 5 * DAY) * 1000)
+        .execute(&mut connection)
+        .await
+        .unwrap();
+    connection.close().await.unwrap();
+
+    let mut package = ZipWriter::new(Cursor::new(Vec::new()));
+    package
+        .start_file("collection.anki2", FileOptions::default())
+        .unwrap();
+    package
+        .write_all(&std::fs::read(file.path()).unwrap())
+        .unwrap();
+    package.finish().unwrap().into_inner()
+}
+
+#[tokio::test]
+async fn decks_become_branches_and_stems() {
+    let files = read_apkg(&package(1_700_000_000).await).await.unwrap();
+
+    let names: Vec<_> = files.iter().map(|file| file.branch.clone()).collect();
+    assert_eq!(
+        names,
+        vec![Some("Default".to_string()), Some("Languages".to_string())]
+    );
+
+    let french = &files[1].decks[0];
+    assert_eq!(french.name, "French");
+    assert_eq!(french.leaves.len(), 2);
+    assert_eq!(
+        (
+            french.leaves[0].front.as_str(),
+            french.leaves[0].back.as_str()
+        ),
+        ("chat", "cat")
+    );
+    assert_eq!(
+        (
+            french.leaves[1].front.as_str(),
+            french.leaves[1].back.as_str()
+        ),
+        ("cat", "chat")
+    );
+}
+
+#[tokio::test]
+async fn review_history_carries_over() {
+    let crt = 1_700_000_000;
+    let files = read_apkg(&package(crt).await).await.unwrap();
+
+    let reviewed = files[1].decks[0].leaves[0].card.clone().unwrap();
+    assert_eq!(reviewed.reps, 2);
+    assert_eq!(reviewed.last_review.timestamp(), crt + 5 * DAY);
+    assert_eq!(reviewed.due.timestamp(), crt + 30 * DAY);
+
+    let new = files[1].decks[0].leaves[1].card.clone().unwrap();
+    assert_eq!(new.reps, 0);
+}
+
+#[tokio::test]
+async fn packages_without_collection_are_rejected() {
+    let mut package = ZipWriter::new(Cursor::new(Vec::new()));
+    package.start_file("media", FileOptions::default()).unwrap();
+    package.write_all(b"{}").unwrap();
+    let package = package.finish().unwrap().into_inner();
+
+    assert!(read_apkg(&package).await.is_err());
+}
+
+#[tokio::test]
+async fn failed_imports_leave_nothing_behind() {
+    let pool = common::pool().await;
+    let user = common::user("alice", &pool).await;
+
+    let mut files = read_apkg(&package(1_700_000_000).await).await.unwrap();
+    // Branches can't be nameless, so the last one fails.
+    files[1].branch = Some(String::new());
+
+    assert!(DeckFile::insert_all(&files, user.id, Utc::now(), &pool)
+        .await
+        .is_err());
+    let branches: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM branches")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(branches, 0);
+}
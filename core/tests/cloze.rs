@@ -0,0 +1,114 @@
+use brainace_core::{
+    cloze::{numbers, render, segments, Segment},
+    deck_file::{DeckFile, LeafEntry},
+    Leaf,
+};
+use chrono::Utc;
+
+const CAPITALS: &str =
+    "{{c1::Paris}} is the capital of {{c2::France::country}}, {{c1::Paris}} again";
+
+#[test]
+fn cloze_numbers_are_found() {
+    assert_eq!(numbers(CAPITALS), vec![1, 2]);
+    assert_eq!(
+        numbers("{{c3::a}} {{c1::b}} {{c0::c}} {{cx::d}} {{c2::e"),
+        vec![1, 3]
+    );
+    assert!(numbers("No clozes here").is_empty());
+}
+
+#[test]
+fn only_the_asked_cloze_is_blanked() {
+    assert_eq!(
+        segments(CAPITALS, 2, false),
+        vec![
+            Segment::Text("Paris is the capital of ".to_string()),
+            Segment::Blank(Some("country".to_string())),
+            Segment::Text(", Paris again".to_string()),
+        ]
+    );
+    assert_eq!(
+        render(CAPITALS, 1, false),
+        "[...] is the capital of France, [...] again"
+    );
+    assert_eq!(
+        render(CAPITALS, 1, true),
+        "Paris is the capital of France, Paris again"
+    );
+    assert_eq!(render("{{c1}} stays", 1, false), "{{c1}} stays");
+}
+
+#[test]
+fn braces_within_clozes_are_matched() {
+    let text = r"$\sqrt{2}$ is {{c1::\sqrt{2}}}, {{c2::\frac{1}{2}::a fraction}}";
+
+    assert_eq!(numbers(text), vec![1, 2]);
+    assert_eq!(
+        render(text, 1, true),
+        r"$\sqrt{2}$ is \sqrt{2}, \frac{1}{2}"
+    );
+    assert_eq!(
+        segments(text, 2, false),
+        vec![
+            Segment::Text(r"$\sqrt{2}$ is \sqrt{2}, ".to_string()),
+            Segment::Blank(Some("a fraction".to_string())),
+        ]
+    );
+}
+
+#[test]
+fn each_cloze_grows_its_own_leaf() {
+    let now = Utc::now();
+
+    let leaves = Leaf::grow(CAPITALS, "", now);
+    assert_eq!(
+        leaves.iter().map(Leaf::cloze).collect::<Vec<_>>(),
+        vec![Some(1), Some(2)]
+    );
+    assert_eq!(
+        leaves[1].prompt(false),
+        "Paris is the capital of [country], Paris again"
+    );
+
+    let basic = Leaf::grow("Front", "Back", now);
+    assert_eq!(basic.len(), 1);
+    assert_eq!(basic[0].cloze(), None);
+    assert_eq!(basic[0].prompt(false), "Front");
+}
+
+#[test]
+fn deck_files_keep_clozes() {
+    let ron = r#"(version:1,decks:[(name:"Capitals",leaves:[(front:"{{c1::Paris}} is in {{c2::France}}",back:"")])])"#;
+    let now = Utc::now();
+
+    let decks = DeckFile::parse(ron).unwrap().into_decks(now);
+    assert_eq!(decks[0].leaves.len(), 2);
+
+    let saved = DeckFile::from(&decks[0]).to_ron().unwrap();
+    let leaves = &DeckFile::parse(&saved).unwrap().into_decks(now)[0].leaves;
+    assert_eq!(
+        leaves.iter().map(Leaf::cloze).collect::<Vec<_>>(),
+        vec![Some(1), Some(2)]
+    );
+}
+
+#[test]
+fn cloze_leaves_are_asked_one_way() {
+    let now = Utc::now();
+
+    let mut leaf = Leaf::grow(CAPITALS, "", now).remove(0);
+    leaf.set_bidirectional(true);
+    assert!(!leaf.is_bidirectional());
+
+    let entry = LeafEntry {
+        front: CAPITALS.to_string(),
+        back: String::new(),
+        card: None,
+        cloze: Some(1),
+        note: None,
+        bidirectional: true,
+        reverse_card: None,
+    };
+    assert!(!entry.into_leaf(now).is_bidirectional());
+}
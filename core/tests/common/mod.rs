@@ -0,0 +1,67 @@
+//! Fixtures shared by the tests that run against a database.
+
+// Each test crate only uses some of them.
+#![allow(dead_code)]
+
+use brainace_core::{auth::User, Leaf};
+use chrono::Utc;
+use sqlx::{sqlite::SqlitePoolOptions, SqlitePool};
+
+/// A fresh in-memory database with every migration run.
+pub async fn pool() -> SqlitePool {
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .unwrap();
+
+    sqlx::migrate!("../migrations").run(&pool).await.unwrap();
+
+    pool
+}
+
+pub async fn user(username: &str, pool: &SqlitePool) -> User {
+    sqlx::query("INSERT INTO users (username, password) VALUES (?, '')")
+        .bind(username)
+        .execute(pool)
+        .await
+        .unwrap();
+
+    User::get_from_username(username.to_string(), pool)
+        .await
+        .unwrap()
+}
+
+pub async fn branch(user_id: i64, pool: &SqlitePool) -> u32 {
+    sqlx::query("INSERT INTO branches (user_id, name) VALUES (?, 'Branch')")
+        .bind(user_id)
+        .execute(pool)
+        .await
+        .unwrap()
+        .last_insert_rowid() as u32
+}
+
+pub async fn stem(branch_id: u32, pool: &SqlitePool) -> u32 {
+    sqlx::query("INSERT INTO stems (branch_id, name) VALUES (?, 'Stem')")
+        .bind(branch_id)
+        .execute(pool)
+        .await
+        .unwrap()
+        .last_insert_rowid() as u32
+}
+
+/// A new user with one branch and one stem, returning the user's id and the stem's.
+pub async fn garden(username: &str, pool: &SqlitePool) -> (i64, u32) {
+    let user = user(username, pool).await;
+    let branch_id = branch(user.id, pool).await;
+
+    (user.id, stem(branch_id, pool).await)
+}
+
+/// Grows a new leaf in `stem_id`, returning its id.
+pub async fn leaf(stem_id: u32, front: &str, pool: &SqlitePool) -> u32 {
+    Leaf::new(front, "Back", Utc::now())
+        .insert(stem_id, &mut pool.acquire().await.unwrap())
+        .await
+        .unwrap() as u32
+}
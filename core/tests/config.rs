@@ -0,0 +1,129 @@
+use brainace_core::{
+    config::{format_interval, BranchConfig, ConfigError},
+    Config, Leaf, Rating, State,
+};
+use chrono::{Duration, Utc};
+
+#[test]
+fn branch_config_is_parsed() {
+    let config = BranchConfig::parse("0.85", "365", "30m 2h 1d").unwrap();
+
+    assert_eq!(config.desired_retention, 0.85);
+    assert_eq!(config.maximum_interval, 365);
+    assert_eq!(config.learning_steps, vec![30, 120, 1440]);
+    assert_eq!(config.format_steps(), "30m 2h 1d");
+}
+
+#[test]
+fn invalid_branch_config_is_rejected() {
+    assert!(matches!(
+        BranchConfig::parse("1.5", "365", ""),
+        Err(ConfigError::DesiredRetention)
+    ));
+    assert!(matches!(
+        BranchConfig::parse("0.9", "0", ""),
+        Err(ConfigError::MaximumInterval)
+    ));
+    assert!(matches!(
+        BranchConfig::parse("0.9", "365", "10m 5x"),
+        Err(ConfigError::LearningStep(step)) if step == "5x"
+    ));
+}
+
+#[test]
+fn learning_steps_schedule_new_leaves() {
+    let config = Config::new(
+        fsrs::Parameters::default().w,
+        &BranchConfig::parse("0.9", "365", "5m 30m").unwrap(),
+    );
+    let now = Utc::now();
+
+    let mut again = Leaf::new("Front", "Back", now);
+    again.review(&config, Rating::Again, now);
+    assert_eq!(again.card().due, now + Duration::minutes(5));
+
+    let mut easy = Leaf::new("Front", "Back", now);
+    easy.review(&config, Rating::Easy, now);
+    assert!(easy.card().due >= now + Duration::days(1));
+}
+
+#[test]
+fn learning_steps_are_walked_through() {
+    let config = Config::new(
+        fsrs::Parameters::default().w,
+        &BranchConfig::parse("0.9", "365", "1m 10m 1h 1d").unwrap(),
+    );
+    let mut now = Utc::now();
+    let mut leaf = Leaf::new("Front", "Back", now);
+
+    for (rating, step) in [
+        (Rating::Good, Duration::minutes(10)),
+        (Rating::Hard, Duration::minutes(35)),
+        (Rating::Good, Duration::hours(1)),
+        (Rating::Good, Duration::days(1)),
+    ] {
+        leaf.review(&config, rating, now);
+        assert_eq!(leaf.card().state, State::Learning);
+        assert_eq!(leaf.card().due, now + step);
+        assert_eq!(leaf.card().scheduled_days, step.num_days());
+        now = leaf.card().due;
+    }
+
+    leaf.review(&config, Rating::Good, now);
+    assert_eq!(leaf.card().state, State::Review);
+}
+
+#[test]
+fn learning_steps_must_get_longer() {
+    assert!(matches!(
+        BranchConfig::parse("0.9", "365", "10m 5m"),
+        Err(ConfigError::LearningStepOrder)
+    ));
+
+    let config = BranchConfig {
+        learning_steps: vec![0, 10],
+        ..Default::default()
+    };
+    assert!(matches!(config.check(), Err(ConfigError::LearningStep(_))));
+}
+
+#[test]
+fn maximum_interval_caps_reviews() {
+    let config = Config::new(
+        fsrs::Parameters::default().w,
+        &BranchConfig::parse("0.9", "3", "").unwrap(),
+    );
+    let now = Utc::now();
+
+    let mut leaf = Leaf::new("Front", "Back", now);
+    for day in 0..5 {
+        leaf.review(&config, Rating::Easy, now + Duration::days(day * 10));
+    }
+
+    assert!(leaf.card().scheduled_days <= 3);
+}
+
+#[test]
+fn intervals_follow_each_rating() {
+    let config = Config::new(
+        fsrs::Parameters::default().w,
+        &BranchConfig::parse("0.9", "365", "5m 30m").unwrap(),
+    );
+    let leaf = Leaf::new("Front", "Back", Utc::now());
+
+    let [again, hard, good, easy] = leaf.intervals(&config, Utc::now());
+
+    assert_eq!(format_interval(again), "5m");
+    assert!(again <= hard && hard <= good && good <= easy);
+    assert_eq!(leaf.card().reps, 0);
+}
+
+#[test]
+fn intervals_are_formatted() {
+    assert_eq!(format_interval(Duration::seconds(20)), "1m");
+    assert_eq!(format_interval(Duration::minutes(10)), "10m");
+    assert_eq!(format_interval(Duration::minutes(90)), "2h");
+    assert_eq!(format_interval(Duration::days(3)), "3d");
+    assert_eq!(format_interval(Duration::days(75)), "3mo");
+    assert_eq!(format_interval(Duration::days(548)), "1.5y");
+}
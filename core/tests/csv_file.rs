@@ -0,0 +1,93 @@
+use brainace_core::{
+    csv_file::{read_leaves, write_leaves, Column, CsvError, CsvOptions},
+    Config, Leaf, Rating,
+};
+use chrono::Utc;
+
+#[test]
+fn quoted_fields_are_read() {
+    let content = "front,back\n\"Pi, rounded\",\"3.14\"\n\"Say \"\"hi\"\"\",\"multi\nline\"\n";
+
+    let preview = read_leaves(content, &CsvOptions::default()).unwrap();
+
+    assert!(preview.errors.is_empty());
+    assert_eq!(preview.leaves[0].front, "Pi, rounded");
+    assert_eq!(preview.leaves[1].front, "Say \"hi\"");
+    assert_eq!(preview.leaves[1].back, "multi\nline");
+}
+
+#[test]
+fn columns_are_mapped() {
+    let content = "id\tback\tfront\n1\tcat\tchat\n";
+    let options = CsvOptions {
+        columns: Some(CsvOptions::parse_columns("ignore,back,front").unwrap()),
+        ..CsvOptions::tsv()
+    };
+
+    let preview = read_leaves(content, &options).unwrap();
+
+    assert_eq!(preview.leaves[0].front, "chat");
+    assert_eq!(preview.leaves[0].back, "cat");
+}
+
+#[test]
+fn row_errors_are_reported() {
+    let content = "front,back,due,stability,difficulty\n\
+        a,b,,,\n\
+        ,d,,,\n\
+        e,f,tomorrow,,\n\
+        g,h,2030-01-01,-1,\n\
+        i,j,2030-01-01,4.5,5\n";
+
+    let preview = read_leaves(content, &CsvOptions::default()).unwrap();
+
+    let lines: Vec<_> = preview.errors.iter().map(|error| error.line).collect();
+    assert_eq!(lines, vec![3, 4, 5]);
+    assert_eq!(preview.leaves.len(), 2);
+    assert!(preview.leaves[0].card.is_none());
+    assert_eq!(preview.leaves[1].card.as_ref().unwrap().stability, 4.5);
+}
+
+#[test]
+fn cloze_rows_may_leave_the_back_empty() {
+    let content = "front,back\n\"{{c1::Paris}} is in {{c2::France}}\",\n";
+
+    let preview = read_leaves(content, &CsvOptions::default()).unwrap();
+
+    assert!(preview.errors.is_empty());
+    assert_eq!(preview.leaves[0].back, "");
+    let leaves = preview.leaves[0].clone().into_leaves(Utc::now());
+    assert_eq!(leaves.len(), 2);
+}
+
+#[test]
+fn missing_columns_are_rejected() {
+    let options = CsvOptions {
+        columns: Some(vec![Column::Front, Column::Due]),
+        ..Default::default()
+    };
+
+    assert!(matches!(
+        read_leaves("a,2030-01-01\n", &options),
+        Err(CsvError::MissingColumn("back"))
+    ));
+}
+
+#[test]
+fn written_leaves_read_back() {
+    let now = Utc::now();
+    let mut reviewed = Leaf::new("Pi, \"rounded\"", "3.14", now);
+    reviewed.review(&Config::default(), Rating::Good, now);
+    let leaves = vec![reviewed, Leaf::new("e", "2.718", now)];
+
+    let content = write_leaves(&leaves, b'\t').unwrap();
+    let preview = read_leaves(&content, &CsvOptions::tsv()).unwrap();
+
+    assert!(preview.errors.is_empty());
+    assert_eq!(preview.leaves[0].front, "Pi, \"rounded\"");
+    assert_eq!(
+        preview.leaves[0].card.as_ref().unwrap().due.timestamp(),
+        leaves[0].card().due.timestamp()
+    );
+    assert!(preview.leaves[1].card.is_none());
+}
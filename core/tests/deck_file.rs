@@ -0,0 +1,93 @@
+use brainace_core::{
+    deck_file::{DeckFile, DeckFileError},
+    Config, Deck, Leaf, Rating,
+};
+use chrono::Utc;
+
+const MATHEMATICAL_CONSTANTS: &str = include_str!("../../mathematical_constants.ron");
+
+#[test]
+fn legacy_fixture_is_migrated() {
+    let file = DeckFile::parse(MATHEMATICAL_CONSTANTS).unwrap();
+
+    assert_eq!(file.version, brainace_core::deck_file::VERSION);
+    assert_eq!(file.decks.len(), 1);
+
+    let deck = &file.decks[0];
+    assert_eq!(deck.name, "Mathematical Constants");
+    assert_eq!(deck.leaves.len(), 5);
+    assert_eq!(deck.leaves[0].front, "The first five digits of Pi are [...]");
+    assert_eq!(deck.leaves[0].back, "3.1415");
+    assert!(deck.leaves.iter().all(|leaf| leaf.card.is_none()));
+}
+
+#[test]
+fn legacy_reviews_are_replayed() {
+    let legacy = r#"Some((name:"Replay",cards:[(card:(front:"Front",back:"Back",fsrs_item:(reviews:[(rating:3,delta_t:0),(rating:3,delta_t:4)]),last_review:Some("2024-02-10T12:00:00Z")))]))"#;
+
+    let file = DeckFile::parse(legacy).unwrap();
+    let card = file.decks[0].leaves[0].card.clone().unwrap();
+
+    assert_eq!(card.reps, 2);
+    assert_eq!(card.last_review.to_rfc3339(), "2024-02-10T12:00:00+00:00");
+}
+
+#[test]
+fn ron_round_trip() {
+    let file = DeckFile::parse(MATHEMATICAL_CONSTANTS).unwrap();
+    let ron = file.to_ron().unwrap();
+
+    assert_eq!(DeckFile::from_ron(&ron).unwrap().to_ron().unwrap(), ron);
+}
+
+#[test]
+fn json_round_trip() {
+    let file = DeckFile::parse(MATHEMATICAL_CONSTANTS).unwrap();
+    let json = file.to_json().unwrap();
+
+    assert_eq!(DeckFile::parse(&json).unwrap().to_json().unwrap(), json);
+}
+
+#[test]
+fn scheduling_state_survives_round_trip() {
+    let now = Utc::now();
+    let mut deck: Deck = DeckFile::parse(MATHEMATICAL_CONSTANTS)
+        .unwrap()
+        .into_decks(now)
+        .remove(0);
+    deck.review(0, &Config::default(), Rating::Good, now);
+
+    let ron = DeckFile::from(&deck).to_ron().unwrap();
+    let deck = DeckFile::from_ron(&ron).unwrap().into_decks(now).remove(0);
+
+    assert_eq!(deck.name, "Mathematical Constants");
+    assert_eq!(deck.leaves[0].card().reps, 1);
+    assert_eq!(deck.leaves[1].card().reps, 0);
+}
+
+#[test]
+fn newer_versions_are_rejected() {
+    let content = r#"(version: 99, decks: [])"#;
+
+    assert!(matches!(
+        DeckFile::parse(content),
+        Err(DeckFileError::UnsupportedVersion(99))
+    ));
+}
+
+#[test]
+fn each_import_grows_notes_of_its_own() {
+    let now = Utc::now();
+    let deck = Deck {
+        name: "Capitals".to_string(),
+        leaves: Leaf::grow("{{c1::Paris}} is in {{c2::France}}", "", now),
+    };
+    let ron = DeckFile::from(&deck).to_ron().unwrap();
+
+    let first = DeckFile::from_ron(&ron).unwrap().into_decks(now).remove(0);
+    let second = DeckFile::from_ron(&ron).unwrap().into_decks(now).remove(0);
+
+    assert!(first.leaves[0].note().is_some());
+    assert_eq!(first.leaves[0].note(), first.leaves[1].note());
+    assert_ne!(first.leaves[0].note(), second.leaves[0].note());
+}
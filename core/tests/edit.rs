@@ -0,0 +1,161 @@
+mod common;
+
+use brainace_core::{Config, Leaf, LeafEdit, Rating, Stem};
+use chrono::Utc;
+use common::pool;
+use sqlx::SqlitePool;
+
+/// The leaves of `stem_id` by id, with their cloze.
+async fn leaves(stem_id: u32, pool: &SqlitePool) -> Vec<(u32, Option<u32>, String)> {
+    let mut leaves: Vec<_> = Stem::get_leaves(stem_id.into(), pool)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|leaf| (leaf.id(), leaf.cloze(), leaf.front()))
+        .collect();
+    leaves.sort();
+
+    leaves
+}
+
+#[tokio::test]
+async fn edited_leaves_keep_their_schedule() {
+    let pool = pool().await;
+    let (_, stem_id) = common::garden("alice", &pool).await;
+    let now = Utc::now();
+
+    let mut leaf = Leaf::new("Hund", "dog", now);
+    leaf.review(&Config::default(), Rating::Good, now);
+    let id = leaf
+        .insert(stem_id, &mut pool.acquire().await.unwrap())
+        .await
+        .unwrap() as u32;
+
+    Leaf::edit(id, "der Hund", "the dog", now, &pool)
+        .await
+        .unwrap();
+
+    let edited = Leaf::get(id, &pool).await.unwrap();
+    assert_eq!(edited.front(), "der Hund");
+    assert_eq!(edited.back(), "the dog");
+    assert_eq!(edited.card().reps, leaf.card().reps);
+}
+
+#[tokio::test]
+async fn clozes_are_edited_together() {
+    let pool = pool().await;
+    let (_, stem_id) = common::garden("alice", &pool).await;
+    let now = Utc::now();
+
+    let front = "{{c1::Paris}} is in {{c2::France}}";
+    Stem::add_leaves(stem_id, &Leaf::grow(front, "", now), &pool)
+        .await
+        .unwrap();
+    let ids: Vec<u32> = leaves(stem_id, &pool).await.iter().map(|l| l.0).collect();
+
+    let front = "{{c1::Paris}} is the capital of {{c3::France}}";
+    let edit = Leaf::edit(ids[0], front, "", now, &pool).await.unwrap();
+    assert_eq!(
+        edit,
+        LeafEdit {
+            grown: 1,
+            pruned: 1,
+            edited_pruned: false
+        }
+    );
+    assert_eq!(
+        leaves(stem_id, &pool).await,
+        vec![
+            (ids[0], Some(1), front.to_string()),
+            (ids[1] + 1, Some(3), front.to_string()),
+        ]
+    );
+
+    // The edited leaf is pruned too once its cloze is taken out, with no new one to take over.
+    let front = "{{c1::Paris}}";
+    let edit = Leaf::edit(ids[1] + 1, front, "", now, &pool).await.unwrap();
+    assert!(edit.edited_pruned);
+    assert_eq!(
+        leaves(stem_id, &pool).await,
+        vec![(ids[0], Some(1), front.to_string())]
+    );
+
+    Leaf::edit(ids[0], "Paris", "France", now, &pool)
+        .await
+        .unwrap();
+    assert_eq!(
+        leaves(stem_id, &pool).await,
+        vec![(ids[0], None, "Paris".to_string())]
+    );
+}
+
+#[tokio::test]
+async fn edited_leaves_take_over_new_clozes() {
+    let pool = pool().await;
+    let (_, stem_id) = common::garden("alice", &pool).await;
+    let now = Utc::now();
+
+    let front = "{{c1::Paris}} is in {{c2::France}}";
+    Stem::add_leaves(stem_id, &Leaf::grow(front, "", now), &pool)
+        .await
+        .unwrap();
+    let ids: Vec<u32> = leaves(stem_id, &pool).await.iter().map(|l| l.0).collect();
+
+    let front = "{{c1::Paris}} is the capital of {{c3::France}}";
+    let edit = Leaf::edit(ids[1], front, "", now, &pool).await.unwrap();
+    assert_eq!(edit, LeafEdit::default());
+    assert_eq!(
+        leaves(stem_id, &pool).await,
+        vec![
+            (ids[0], Some(1), front.to_string()),
+            (ids[1], Some(3), front.to_string()),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn only_clozes_of_the_same_note_are_edited_together() {
+    let pool = pool().await;
+    let (_, stem_id) = common::garden("alice", &pool).await;
+    let now = Utc::now();
+
+    let front = "{{c1::Paris}} is in {{c2::France}}";
+    let mut grown = Leaf::grow(front, "", now);
+    grown.extend(Leaf::grow(front, "", now));
+    Stem::add_leaves(stem_id, &grown, &pool).await.unwrap();
+    let ids: Vec<u32> = leaves(stem_id, &pool).await.iter().map(|l| l.0).collect();
+
+    let edited = "{{c1::Paris}}";
+    Leaf::edit(ids[0], edited, "", now, &pool).await.unwrap();
+    assert_eq!(
+        leaves(stem_id, &pool).await,
+        vec![
+            (ids[0], Some(1), edited.to_string()),
+            (ids[2], Some(1), front.to_string()),
+            (ids[3], Some(2), front.to_string()),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn leaves_edited_into_clozes_are_asked_one_way() {
+    let pool = pool().await;
+    let (_, stem_id) = common::garden("alice", &pool).await;
+    let now = Utc::now();
+
+    let mut leaf = Leaf::new("Paris", "France", now);
+    leaf.set_bidirectional(true);
+    let id = leaf
+        .insert(stem_id, &mut pool.acquire().await.unwrap())
+        .await
+        .unwrap() as u32;
+
+    Leaf::edit(id, "{{c1::Paris}} is in France", "", now, &pool)
+        .await
+        .unwrap();
+
+    let edited = Leaf::get(id, &pool).await.unwrap();
+    assert_eq!(edited.cloze(), Some(1));
+    assert!(!edited.is_bidirectional());
+    assert_eq!(edited.version(), 1);
+}
@@ -0,0 +1,94 @@
+use brainace_core::{
+    markup::{render, render_cloze},
+    Leaf,
+};
+use chrono::Utc;
+
+#[test]
+fn markdown_is_rendered() {
+    assert_eq!(
+        render("**Bold** and `code`"),
+        "<p><strong>Bold</strong> and <code>code</code></p>\n"
+    );
+    assert!(render("```rust\nfn main() {}\n```").starts_with("<pre><code>fn main() {}"));
+}
+
+#[test]
+fn math_is_left_for_katex() {
+    assert_eq!(
+        render("$e^{i\\pi} + 1 = 0$"),
+        "<p><span class=\"math math-inline\">e^{i\\pi} + 1 = 0</span></p>\n"
+    );
+    assert!(render("$$\\int_0^1 x \\, dx$$").contains("<span class=\"math math-display\">"));
+}
+
+#[test]
+fn unsafe_html_is_removed() {
+    let html = render(
+        "<script>alert(1)</script><a href=\"javascript:alert(1)\" onclick=\"alert(1)\">Link</a>",
+    );
+
+    assert!(!html.contains("script"));
+    assert!(!html.contains("javascript"));
+    assert!(!html.contains("onclick"));
+    assert!(html.contains("Link"));
+}
+
+#[test]
+fn clozes_are_marked() {
+    let text = "{{c1::$x^2$}} is the square of {{c2::x::a variable}}";
+
+    assert_eq!(
+        render_cloze(text, 2, false),
+        "<p><span class=\"math math-inline\">x^2</span> is the square of <span class=\"cloze\">[a variable]</span></p>\n"
+    );
+    assert!(render_cloze(text, 1, true).starts_with(
+        "<p><span class=\"cloze\"><span class=\"math math-inline\">x^2</span></span>"
+    ));
+}
+
+#[test]
+fn clozes_within_math_are_boxed() {
+    let text = r"$\pi = {{c1::3.14}}$ and $$e = {{c2::2.72::e}}$$, not \${{c1::3.14}}";
+
+    assert_eq!(
+        render_cloze(text, 1, false),
+        "<p><span class=\"math math-inline\">\\pi = \\boxed{?}</span> and <span class=\"math math-display\">e = 2.72</span>, not $<span class=\"cloze\">[...]</span></p>\n"
+    );
+    assert!(render_cloze(text, 1, true)
+        .starts_with("<p><span class=\"math math-inline\">\\pi = \\boxed{3.14}</span>"));
+    assert!(render_cloze(text, 2, false)
+        .contains("<span class=\"math math-display\">e = \\boxed{e}</span>"));
+}
+
+#[test]
+fn leaves_are_rendered_as_asked() {
+    let now = Utc::now();
+
+    let mut leaf = Leaf::new("*Hund*", "dog", now);
+    leaf.set_bidirectional(true);
+
+    let forward = leaf.clone().with_markup();
+    let markup = forward.markup().unwrap();
+    assert_eq!(markup.prompt, "<p><em>Hund</em></p>\n");
+    assert_eq!(markup.prompt, markup.revealed);
+    assert_eq!(markup.answer, "<p>dog</p>\n");
+
+    let reverse = leaf
+        .asked(brainace_core::Direction::Reverse)
+        .render_markup();
+    assert_eq!(reverse.prompt, "<p>dog</p>\n");
+    assert_eq!(reverse.answer, "<p><em>Hund</em></p>\n");
+
+    let cloze = &Leaf::grow("{{c1::Paris}} is in France", "", now)[0];
+    let markup = cloze.render_markup();
+    assert_eq!(
+        markup.prompt,
+        "<p><span class=\"cloze\">[...]</span> is in France</p>\n"
+    );
+    assert_eq!(
+        markup.revealed,
+        "<p><span class=\"cloze\">Paris</span> is in France</p>\n"
+    );
+    assert_eq!(markup.answer, "");
+}
@@ -0,0 +1,116 @@
+mod common;
+
+use brainace_core::{
+    auth::AccessError,
+    markup::render,
+    media::{accepted_types, parse_file_name, Kind, Media, MediaError},
+};
+use common::{pool, user};
+
+const HELLO: &str = "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824";
+
+#[test]
+fn uploads_are_checked_and_hashed() {
+    let media = Media::new("hello.png", "image/png", b"hello").unwrap();
+    assert_eq!(media.hash, HELLO);
+    assert_eq!(media.size, 5);
+    assert_eq!(media.file_name(), format!("{HELLO}.png"));
+    assert_eq!(
+        media.reference(),
+        format!("![hello.png](media:{HELLO}.png)")
+    );
+
+    assert!(matches!(
+        Media::new("page.svg", "image/svg+xml", b"<svg/>"),
+        Err(MediaError::Unsupported(_))
+    ));
+    assert!(matches!(
+        Media::new("empty.mp3", "audio/mpeg", b""),
+        Err(MediaError::Empty)
+    ));
+}
+
+#[test]
+fn file_names_are_parsed() {
+    assert_eq!(
+        parse_file_name(&format!("{HELLO}.mp3")),
+        Some((HELLO, Kind::Audio))
+    );
+    assert_eq!(parse_file_name(&format!("{HELLO}.svg")), None);
+    assert_eq!(parse_file_name("../../etc/passwd.png"), None);
+    assert_eq!(parse_file_name(HELLO), None);
+}
+
+#[test]
+fn content_is_stored_once() {
+    let dir = tempfile::tempdir().unwrap();
+    let media = Media::new("hello.png", "image/png", b"hello").unwrap();
+
+    let path = media.store(b"hello", dir.path()).unwrap();
+    assert_eq!(path, dir.path().join("2c").join(HELLO));
+    assert_eq!(std::fs::read(&path).unwrap(), b"hello");
+
+    let again = Media::new("copy.png", "image/png", b"hello").unwrap();
+    assert_eq!(again.store(b"hello", dir.path()).unwrap(), path);
+}
+
+#[test]
+fn only_the_stored_file_is_left() {
+    let dir = tempfile::tempdir().unwrap();
+    let media = Media::new("hello.png", "image/png", b"hello").unwrap();
+
+    media.store(b"hello", dir.path()).unwrap();
+    media.store(b"hello", dir.path()).unwrap();
+
+    let files: Vec<_> = std::fs::read_dir(dir.path().join("2c"))
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name())
+        .collect();
+    assert_eq!(files, vec![HELLO]);
+}
+
+#[test]
+fn accepted_types_are_listed() {
+    let types = accepted_types();
+
+    assert_eq!(types.split(',').count(), 9);
+    assert!(types.starts_with("image/png,image/jpeg,"));
+    assert!(!types.contains('*'));
+}
+
+#[tokio::test]
+async fn media_is_only_found_for_its_uploaders() {
+    let pool = pool().await;
+    let alice = user("alice", &pool).await;
+    let bob = user("bob", &pool).await;
+
+    let media = Media::new("hello.png", "image/png", b"hello").unwrap();
+    media.insert(alice.id, &pool).await.unwrap();
+    Media::new("renamed.png", "image/png", b"hello")
+        .unwrap()
+        .insert(alice.id, &pool)
+        .await
+        .unwrap();
+
+    assert_eq!(Media::get(HELLO, alice.id, &pool).await.unwrap(), media);
+    assert!(matches!(
+        Media::get(HELLO, bob.id, &pool).await,
+        Err(AccessError::NotFound)
+    ));
+
+    media.insert(bob.id, &pool).await.unwrap();
+    assert!(Media::get(HELLO, bob.id, &pool).await.is_ok());
+}
+
+#[test]
+fn references_are_rendered() {
+    assert_eq!(
+        render(&format!("![diagram](media:{HELLO}.png)")),
+        format!("<p><img src=\"/media/{HELLO}.png\" alt=\"diagram\"></p>\n")
+    );
+    assert_eq!(
+        render(&format!("Listen: ![clip](media:{HELLO}.mp3)")),
+        format!("<p>Listen: <audio controls=\"\" src=\"/media/{HELLO}.mp3\"></audio></p>\n")
+    );
+    assert!(!render("![x](media:../secret.png)").contains("secret"));
+}
@@ -0,0 +1,100 @@
+use brainace_core::{
+    optimizer::{fitted_reviews, loss, optimize, MIN_REVIEWS},
+    review_log::ReviewLog,
+    Card, Rating,
+};
+use chrono::{DateTime, Duration, TimeZone, Utc};
+
+/// A small linear congruential generator, so histories are the same on every run.
+struct Lcg(u64);
+
+impl Lcg {
+    fn next(&mut self) -> f64 {
+        self.0 = self
+            .0
+            .wrapping_mul(6_364_136_223_846_793_005)
+            .wrapping_add(1_442_695_040_888_963_407);
+        (self.0 >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+fn log(leaf_id: u32, rating: Rating, reviewed_at: DateTime<Utc>) -> ReviewLog {
+    ReviewLog::new(
+        leaf_id,
+        1,
+        rating,
+        Card::default(),
+        &Card::default(),
+        reviewed_at,
+    )
+}
+
+/// Simulates a learner whose memories last much longer than the default weights predict.
+fn history(leaves: u32) -> Vec<ReviewLog> {
+    let mut random = Lcg(42);
+    let mut logs = Vec::new();
+
+    for leaf_id in 0..leaves {
+        let mut now = Utc.with_ymd_and_hms(2024, 1, 1, 8, 0, 0).unwrap();
+        let mut stability: f64 = 20.0;
+        logs.push(log(leaf_id, Rating::Good, now));
+
+        for _ in 0..5 {
+            let elapsed = (stability * (0.5 + random.next())).ceil().max(1.0);
+            now += Duration::days(elapsed as i64);
+
+            let retrievability = (1.0 + 19.0 / 81.0 * elapsed / stability).powf(-0.5);
+            if random.next() < retrievability {
+                logs.push(log(leaf_id, Rating::Good, now));
+                stability *= 2.5;
+            } else {
+                logs.push(log(leaf_id, Rating::Again, now));
+                stability = (stability / 3.0).max(1.0);
+            }
+        }
+    }
+
+    logs
+}
+
+#[test]
+fn short_histories_are_not_optimized() {
+    let logs = history(MIN_REVIEWS as u32 / 5 - 1);
+
+    assert!(optimize(&logs).is_none());
+}
+
+#[test]
+fn optimized_weights_fit_better() {
+    let logs = history(200);
+
+    let default = fsrs::Parameters::default().w;
+    let optimized = optimize(&logs).unwrap();
+
+    assert!(loss(&optimized, &logs).unwrap() < loss(&default, &logs).unwrap());
+}
+
+#[test]
+fn same_day_reviews_are_skipped() {
+    let now = Utc.with_ymd_and_hms(2024, 1, 1, 8, 0, 0).unwrap();
+    let logs = vec![
+        log(1, Rating::Again, now),
+        log(1, Rating::Good, now + Duration::minutes(10)),
+    ];
+
+    assert!(loss(&fsrs::Parameters::default().w, &logs).is_none());
+}
+
+#[test]
+fn only_fitted_reviews_are_counted() {
+    let now = Utc.with_ymd_and_hms(2024, 1, 1, 8, 0, 0).unwrap();
+    let logs = vec![
+        log(1, Rating::Again, now),
+        log(1, Rating::Good, now + Duration::minutes(10)),
+        log(1, Rating::Good, now + Duration::days(1)),
+        log(1, Rating::Good, now + Duration::days(4)),
+        log(2, Rating::Good, now),
+    ];
+
+    assert_eq!(fitted_reviews(&logs), 2);
+}
@@ -0,0 +1,240 @@
+mod common;
+
+use brainace_core::{
+    queue::{DueQuery, LeafCounts, QueueLimits, Scope},
+    Config, Direction, Leaf, Rating,
+};
+use chrono::{DateTime, Duration, Utc};
+use common::{garden, pool};
+use sqlx::SqlitePool;
+
+/// Grows a leaf last reviewed at `reviewed_at`, or a new one.
+async fn leaf(stem_id: u32, front: &str, reviewed_at: Option<DateTime<Utc>>, pool: &SqlitePool) {
+    let now = Utc::now();
+    let mut leaf = Leaf::new(front, "Back", now);
+    if let Some(reviewed_at) = reviewed_at {
+        leaf.review(&Config::default(), Rating::Good, reviewed_at);
+    }
+
+    leaf.insert(stem_id, &mut pool.acquire().await.unwrap())
+        .await
+        .unwrap();
+}
+
+fn query(user_id: i64, scope: Scope, limits: QueueLimits) -> DueQuery {
+    let now = Utc::now();
+
+    DueQuery {
+        user_id,
+        scope,
+        now,
+        day_end: now,
+        limits,
+    }
+}
+
+fn fronts(leaves: &[Leaf]) -> Vec<String> {
+    leaves.iter().map(Leaf::front).collect()
+}
+
+#[tokio::test]
+async fn overdue_leaves_come_before_new_ones() {
+    let pool = pool().await;
+    let (user_id, stem_id) = garden("alice", &pool).await;
+    let now = Utc::now();
+
+    leaf(stem_id, "new 1", None, &pool).await;
+    leaf(stem_id, "overdue", Some(now - Duration::days(300)), &pool).await;
+    leaf(stem_id, "not due", Some(now), &pool).await;
+    leaf(stem_id, "new 2", None, &pool).await;
+    leaf(
+        stem_id,
+        "very overdue",
+        Some(now - Duration::days(400)),
+        &pool,
+    )
+    .await;
+
+    let leaves = query(user_id, Scope::All, QueueLimits::default())
+        .leaves(0, 10, &pool)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        fronts(&leaves),
+        vec!["very overdue", "overdue", "new 1", "new 2"]
+    );
+}
+
+#[tokio::test]
+async fn limits_and_pages_are_applied() {
+    let pool = pool().await;
+    let (user_id, stem_id) = garden("alice", &pool).await;
+    let now = Utc::now();
+
+    for i in 0..3 {
+        leaf(stem_id, &format!("new {i}"), None, &pool).await;
+        leaf(
+            stem_id,
+            &format!("review {i}"),
+            Some(now - Duration::days(300 - i)),
+            &pool,
+        )
+        .await;
+    }
+
+    let limits = QueueLimits { new: 1, review: 2 };
+    let first = query(user_id, Scope::All, limits)
+        .leaves(0, 2, &pool)
+        .await
+        .unwrap();
+    let second = query(user_id, Scope::All, limits)
+        .leaves(2, 2, &pool)
+        .await
+        .unwrap();
+
+    assert_eq!(fronts(&first), vec!["review 0", "review 1"]);
+    assert_eq!(fronts(&second), vec!["new 0"]);
+}
+
+#[tokio::test]
+async fn other_users_leaves_are_left_out() {
+    let pool = pool().await;
+    let (alice, _) = garden("alice", &pool).await;
+    let (_, bob_stem) = garden("bob", &pool).await;
+    leaf(bob_stem, "bob's", None, &pool).await;
+
+    let leaves = query(alice, Scope::All, QueueLimits::default())
+        .leaves(0, 10, &pool)
+        .await
+        .unwrap();
+
+    assert!(leaves.is_empty());
+}
+
+#[tokio::test]
+async fn queues_can_be_scoped() {
+    let pool = pool().await;
+    let (user_id, first_stem) = garden("alice", &pool).await;
+    let second_stem = sqlx::query(
+        "INSERT INTO stems (branch_id, name) SELECT branch_id, 'Other' FROM stems WHERE id = ?",
+    )
+    .bind(first_stem)
+    .execute(&pool)
+    .await
+    .unwrap()
+    .last_insert_rowid() as u32;
+    let branch_id: u32 = sqlx::query_scalar("SELECT branch_id FROM stems WHERE id = ?")
+        .bind(first_stem)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+
+    leaf(first_stem, "first", None, &pool).await;
+    leaf(second_stem, "second", None, &pool).await;
+
+    let stem = query(user_id, Scope::Stem(second_stem), QueueLimits::default())
+        .leaves(0, 10, &pool)
+        .await
+        .unwrap();
+    let branch = query(user_id, Scope::Branch(branch_id), QueueLimits::default())
+        .leaves(0, 10, &pool)
+        .await
+        .unwrap();
+    let elsewhere = query(
+        user_id,
+        Scope::Branch(branch_id + 1),
+        QueueLimits::default(),
+    )
+    .leaves(0, 10, &pool)
+    .await
+    .unwrap();
+
+    assert_eq!(fronts(&stem), vec!["second"]);
+    assert_eq!(fronts(&branch), vec!["first", "second"]);
+    assert!(elsewhere.is_empty());
+}
+
+#[tokio::test]
+async fn leaves_are_counted_per_branch_and_stem() {
+    let pool = pool().await;
+    let (user_id, stem_id) = garden("alice", &pool).await;
+    let branch_id: u32 = sqlx::query_scalar("SELECT branch_id FROM stems WHERE id = ?")
+        .bind(stem_id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    let empty_branch = sqlx::query("INSERT INTO branches (user_id, name) VALUES (?, 'Empty')")
+        .bind(user_id)
+        .execute(&pool)
+        .await
+        .unwrap()
+        .last_insert_rowid() as u32;
+    let now = Utc::now();
+
+    leaf(stem_id, "new", None, &pool).await;
+    leaf(stem_id, "overdue", Some(now - Duration::days(300)), &pool).await;
+    leaf(stem_id, "reviewed", Some(now), &pool).await;
+
+    let counts = LeafCounts {
+        due: 1,
+        new: 1,
+        total: 3,
+    };
+
+    let branches = LeafCounts::per_branch(user_id, now, now, &pool)
+        .await
+        .unwrap();
+    assert_eq!(branches.len(), 2);
+    assert_eq!(branches[&branch_id], counts);
+    assert_eq!(branches[&empty_branch], LeafCounts::default());
+
+    let stems = LeafCounts::per_stem(branch_id, now, now, &pool)
+        .await
+        .unwrap();
+    assert_eq!(stems.len(), 1);
+    assert_eq!(stems[&stem_id], counts);
+}
+
+#[tokio::test]
+async fn both_directions_of_bidirectional_leaves_are_queued() {
+    let pool = pool().await;
+    let (user_id, stem_id) = garden("alice", &pool).await;
+    let now = Utc::now();
+
+    let mut bidirectional = Leaf::new("Hund", "dog", now);
+    bidirectional.set_bidirectional(true);
+    bidirectional
+        .insert(stem_id, &mut pool.acquire().await.unwrap())
+        .await
+        .unwrap();
+    leaf(stem_id, "basic", None, &pool).await;
+
+    let due = query(user_id, Scope::All, QueueLimits::default())
+        .leaves(0, 10, &pool)
+        .await
+        .unwrap();
+    let asked: Vec<_> = due
+        .iter()
+        .map(|leaf| (leaf.prompt(false), leaf.direction()))
+        .collect();
+    assert_eq!(
+        asked,
+        vec![
+            ("Hund".to_string(), Direction::Forward),
+            ("dog".to_string(), Direction::Reverse),
+            ("basic".to_string(), Direction::Forward),
+        ]
+    );
+    assert_eq!(due[1].answer(), "Hund");
+
+    let stems = LeafCounts::per_stem(1, now, now, &pool).await.unwrap();
+    assert_eq!(
+        stems[&stem_id],
+        LeafCounts {
+            due: 0,
+            new: 3,
+            total: 2,
+        }
+    );
+}
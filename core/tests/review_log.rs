@@ -0,0 +1,364 @@
+mod common;
+
+use brainace_core::{
+    review_log::{ReviewError, ReviewLog},
+    Config, Direction, Rating, SqlLeaf, State,
+};
+use chrono::{Duration, Utc};
+use common::pool;
+use sqlx::SqlitePool;
+
+/// Grows a user with one leaf, returning their ids.
+async fn garden(pool: &SqlitePool) -> (i64, u32) {
+    let (user_id, stem_id) = common::garden("alice", pool).await;
+
+    (user_id, common::leaf(stem_id, "Front", pool).await)
+}
+
+async fn leaf(id: u32, pool: &SqlitePool) -> brainace_core::Leaf {
+    sqlx::query_as::<_, SqlLeaf>("SELECT * FROM leaves WHERE id = ?")
+        .bind(id)
+        .fetch_one(pool)
+        .await
+        .unwrap()
+        .into_leaf()
+}
+
+#[tokio::test]
+async fn reviews_are_logged_with_the_card() {
+    let pool = pool().await;
+    let (user_id, leaf_id) = garden(&pool).await;
+    let config = Config::default();
+    let now = Utc::now();
+
+    let mut reviewed = leaf(leaf_id, &pool).await;
+    reviewed
+        .record_review(user_id, &config, Rating::Good, now, &pool)
+        .await
+        .unwrap();
+    reviewed
+        .record_review(
+            user_id,
+            &config,
+            Rating::Again,
+            now + Duration::days(3),
+            &pool,
+        )
+        .await
+        .unwrap();
+
+    let stored = leaf(leaf_id, &pool).await;
+    assert_eq!(stored.card().reps, 2);
+
+    let logs = ReviewLog::for_leaf(leaf_id, &pool).await.unwrap();
+    assert_eq!(logs.len(), 2);
+    assert_eq!(logs[0].rating, Rating::Good);
+    assert_eq!(logs[0].state, State::New);
+    assert_eq!(logs[0].previous_card.reps, 0);
+    assert_eq!(logs[1].rating, Rating::Again);
+    assert_eq!(logs[1].previous_card.reps, 1);
+    assert_eq!(
+        logs[1].reviewed_at.timestamp(),
+        (now + Duration::days(3)).timestamp()
+    );
+
+    let last = ReviewLog::last_for_user(user_id, &pool)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(last.id, logs[1].id);
+    assert_eq!(ReviewLog::for_user(user_id, &pool).await.unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn failed_reviews_leave_no_trace() {
+    let pool = pool().await;
+    let (user_id, leaf_id) = garden(&pool).await;
+    sqlx::query("DROP TABLE review_logs")
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let mut reviewed = leaf(leaf_id, &pool).await;
+    let result = reviewed
+        .record_review(user_id, &Config::default(), Rating::Good, Utc::now(), &pool)
+        .await;
+
+    assert!(result.is_err());
+    assert_eq!(leaf(leaf_id, &pool).await.card().reps, 0);
+}
+
+#[tokio::test]
+async fn the_last_review_can_be_undone() {
+    let pool = pool().await;
+    let (user_id, leaf_id) = garden(&pool).await;
+    let config = Config::default();
+    let now = Utc::now();
+
+    let mut reviewed = leaf(leaf_id, &pool).await;
+    reviewed
+        .record_review(user_id, &config, Rating::Good, now, &pool)
+        .await
+        .unwrap();
+    let before = leaf(leaf_id, &pool).await.card().clone();
+    let log = reviewed
+        .record_review(
+            user_id,
+            &config,
+            Rating::Again,
+            now + Duration::days(3),
+            &pool,
+        )
+        .await
+        .unwrap();
+
+    let (undone, version) = ReviewLog::undo(user_id, log.id, &pool)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(undone.rating, Rating::Again);
+    assert_eq!(version, 3);
+
+    let stored = leaf(leaf_id, &pool).await;
+    assert_eq!(stored.card().reps, before.reps);
+    assert_eq!(stored.card().due, before.due);
+
+    let logs = ReviewLog::for_leaf(leaf_id, &pool).await.unwrap();
+    assert_eq!(logs.len(), 1);
+    assert_eq!(logs[0].rating, Rating::Good);
+}
+
+#[tokio::test]
+async fn nothing_to_undo_without_reviews() {
+    let pool = pool().await;
+    let (user_id, leaf_id) = garden(&pool).await;
+
+    let undone = ReviewLog::undo(user_id, 1, &pool).await.unwrap();
+
+    assert!(undone.is_none());
+    assert_eq!(leaf(leaf_id, &pool).await.card().reps, 0);
+}
+
+#[tokio::test]
+async fn reviews_are_undone_by_their_log() {
+    let pool = pool().await;
+    let (user_id, leaf_id) = garden(&pool).await;
+    let config = Config::default();
+    let now = Utc::now();
+
+    let mut reviewed = leaf(leaf_id, &pool).await;
+    let first = reviewed
+        .record_review(user_id, &config, Rating::Good, now, &pool)
+        .await
+        .unwrap();
+    let second = reviewed
+        .record_review(
+            user_id,
+            &config,
+            Rating::Again,
+            now + Duration::days(3),
+            &pool,
+        )
+        .await
+        .unwrap();
+
+    // A review that was followed by another one is left alone.
+    assert!(ReviewLog::undo(user_id, first.id, &pool)
+        .await
+        .unwrap()
+        .is_none());
+    assert!(ReviewLog::undo(user_id + 1, second.id, &pool)
+        .await
+        .unwrap()
+        .is_none());
+
+    let (undone, _) = ReviewLog::undo(user_id, second.id, &pool)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(undone.rating, Rating::Again);
+    assert_eq!(leaf(leaf_id, &pool).await.card().reps, 1);
+    assert_eq!(ReviewLog::for_leaf(leaf_id, &pool).await.unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn stale_leaves_are_not_reviewed_twice() {
+    let pool = pool().await;
+    let (user_id, leaf_id) = garden(&pool).await;
+    let config = Config::default();
+    let now = Utc::now();
+
+    let mut first_tab = leaf(leaf_id, &pool).await;
+    let mut second_tab = leaf(leaf_id, &pool).await;
+    first_tab
+        .record_review(user_id, &config, Rating::Good, now, &pool)
+        .await
+        .unwrap();
+    let result = second_tab
+        .record_review(user_id, &config, Rating::Again, now, &pool)
+        .await;
+
+    assert!(matches!(result, Err(ReviewError::Conflict)));
+    assert_eq!(second_tab.card().reps, 0);
+    assert_eq!(leaf(leaf_id, &pool).await.card().reps, 1);
+    assert_eq!(ReviewLog::for_leaf(leaf_id, &pool).await.unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn leaves_undone_in_the_meantime_are_not_reviewed() {
+    let pool = pool().await;
+    let (user_id, leaf_id) = garden(&pool).await;
+    let config = Config::default();
+    let now = Utc::now();
+
+    let mut first_tab = leaf(leaf_id, &pool).await;
+    let mut second_tab = leaf(leaf_id, &pool).await;
+    let log = first_tab
+        .record_review(user_id, &config, Rating::Good, now, &pool)
+        .await
+        .unwrap();
+    ReviewLog::undo(user_id, log.id, &pool)
+        .await
+        .unwrap()
+        .unwrap();
+
+    // The card is back to how the second tab saw it, but it changed twice since.
+    let result = second_tab
+        .record_review(user_id, &config, Rating::Again, now, &pool)
+        .await;
+
+    assert!(matches!(result, Err(ReviewError::Conflict)));
+    assert_eq!(leaf(leaf_id, &pool).await.version(), 2);
+    assert!(ReviewLog::for_leaf(leaf_id, &pool)
+        .await
+        .unwrap()
+        .is_empty());
+}
+
+#[tokio::test]
+async fn undone_leaves_can_be_reviewed_again() {
+    let pool = pool().await;
+    let (user_id, leaf_id) = garden(&pool).await;
+    let config = Config::default();
+    let now = Utc::now();
+
+    // The client keeps the leaf as it was loaded, while the server reviews its own copy.
+    let mut shown = leaf(leaf_id, &pool).await;
+    let log = shown
+        .clone()
+        .record_review(user_id, &config, Rating::Again, now, &pool)
+        .await
+        .unwrap();
+    let (_, version) = ReviewLog::undo(user_id, log.id, &pool)
+        .await
+        .unwrap()
+        .unwrap();
+
+    shown.set_version(version);
+    shown
+        .record_review(user_id, &config, Rating::Good, now, &pool)
+        .await
+        .unwrap();
+
+    let logs = ReviewLog::for_leaf(leaf_id, &pool).await.unwrap();
+    assert_eq!(logs.len(), 1);
+    assert_eq!(logs[0].rating, Rating::Good);
+}
+
+#[tokio::test]
+async fn directions_are_reviewed_and_undone_on_their_own() {
+    let pool = pool().await;
+    let (user_id, leaf_id) = garden(&pool).await;
+    let config = Config::default();
+    let now = Utc::now();
+
+    sqlx::query("UPDATE leaves SET reverse_card = ? WHERE id = ?")
+        .bind(sqlx::types::Json(brainace_core::Card::default()))
+        .bind(leaf_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let mut reverse = leaf(leaf_id, &pool).await.asked(Direction::Reverse);
+    let log = reverse
+        .record_review(user_id, &config, Rating::Good, now, &pool)
+        .await
+        .unwrap();
+    assert_eq!(log.direction, Direction::Reverse);
+
+    let stored = leaf(leaf_id, &pool).await;
+    assert_eq!(stored.card().reps, 0);
+    assert_eq!(stored.reverse_card().unwrap().reps, 1);
+
+    let (undone, _) = ReviewLog::undo(user_id, log.id, &pool)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(undone.direction, Direction::Reverse);
+    assert_eq!(leaf(leaf_id, &pool).await.reverse_card().unwrap().reps, 0);
+}
+
+#[tokio::test]
+async fn both_directions_can_be_rated_in_one_session() {
+    let pool = pool().await;
+    let (user_id, leaf_id) = garden(&pool).await;
+    let config = Config::default();
+    let now = Utc::now();
+
+    sqlx::query("UPDATE leaves SET reverse_card = ? WHERE id = ?")
+        .bind(sqlx::types::Json(brainace_core::Card::default()))
+        .bind(leaf_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let mut forward = leaf(leaf_id, &pool).await;
+    let mut reverse = forward.clone().asked(Direction::Reverse);
+    forward
+        .record_review(user_id, &config, Rating::Good, now, &pool)
+        .await
+        .unwrap();
+
+    // Both directions share the version, so the other one catches up with the rating.
+    reverse.set_version(forward.version());
+    reverse
+        .record_review(user_id, &config, Rating::Good, now, &pool)
+        .await
+        .unwrap();
+
+    let stored = leaf(leaf_id, &pool).await;
+    assert_eq!(stored.card().reps, 1);
+    assert_eq!(stored.reverse_card().unwrap().reps, 1);
+}
+
+#[tokio::test]
+async fn reverse_reviews_of_one_way_leaves_are_not_undone() {
+    let pool = pool().await;
+    let (user_id, leaf_id) = garden(&pool).await;
+    let now = Utc::now();
+
+    sqlx::query("UPDATE leaves SET reverse_card = ? WHERE id = ?")
+        .bind(sqlx::types::Json(brainace_core::Card::default()))
+        .bind(leaf_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+    let log = leaf(leaf_id, &pool)
+        .await
+        .asked(Direction::Reverse)
+        .record_review(user_id, &Config::default(), Rating::Good, now, &pool)
+        .await
+        .unwrap();
+    sqlx::query("UPDATE leaves SET reverse_card = NULL WHERE id = ?")
+        .bind(leaf_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    assert!(ReviewLog::undo(user_id, log.id, &pool)
+        .await
+        .unwrap()
+        .is_none());
+    assert!(!leaf(leaf_id, &pool).await.is_bidirectional());
+    assert_eq!(ReviewLog::for_leaf(leaf_id, &pool).await.unwrap().len(), 1);
+}
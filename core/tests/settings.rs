@@ -0,0 +1,143 @@
+mod common;
+
+use brainace_core::{
+    settings::{DailyCounts, SettingsError, StudySettings},
+    Config, Direction, Leaf, Rating,
+};
+use chrono::{Duration, NaiveDate, TimeZone, Utc};
+
+fn paris() -> StudySettings {
+    StudySettings::parse("10", "100", "4", "Europe/Paris").unwrap()
+}
+
+#[test]
+fn late_reviews_count_towards_the_day_before() {
+    let settings = paris();
+
+    // 02:30 in Paris, before the day starts at 04:00.
+    let night = Utc.with_ymd_and_hms(2024, 6, 10, 0, 30, 0).unwrap();
+    assert_eq!(
+        settings.study_date(night),
+        NaiveDate::from_ymd_opt(2024, 6, 9).unwrap()
+    );
+    assert_eq!(
+        settings.day_start(night),
+        Utc.with_ymd_and_hms(2024, 6, 9, 2, 0, 0).unwrap()
+    );
+    assert_eq!(
+        settings.day_end(night),
+        Utc.with_ymd_and_hms(2024, 6, 10, 2, 0, 0).unwrap()
+    );
+}
+
+#[test]
+fn daylight_saving_changes_shorten_the_day() {
+    let settings = paris();
+    let before_change = Utc.with_ymd_and_hms(2024, 3, 30, 12, 0, 0).unwrap();
+
+    assert_eq!(
+        settings.day_end(before_change) - settings.day_start(before_change),
+        Duration::hours(23)
+    );
+}
+
+#[test]
+fn invalid_settings_are_rejected() {
+    assert!(matches!(
+        StudySettings::parse("-1", "100", "4", "UTC"),
+        Err(SettingsError::Limit)
+    ));
+    assert!(matches!(
+        StudySettings::parse("10", "100", "24", "UTC"),
+        Err(SettingsError::DayStartsAt)
+    ));
+    assert!(matches!(
+        StudySettings::parse("10", "100", "4", "Mars/Olympus"),
+        Err(SettingsError::Timezone(_))
+    ));
+}
+
+#[test]
+fn limits_shrink_as_leaves_are_studied() {
+    let limits = paris().limits(DailyCounts {
+        new: 4,
+        review: 120,
+    });
+
+    assert_eq!((limits.new, limits.review), (6, 0));
+}
+
+#[tokio::test]
+async fn counters_come_from_the_review_log() {
+    let pool = common::pool().await;
+    let (user_id, stem_id) = common::garden("alice", &pool).await;
+
+    let config = Config::default();
+    let start = Utc::now() - Duration::days(10);
+    for front in ["a", "b"] {
+        let mut leaf = Leaf::new(front, "Back", start);
+        let id = leaf
+            .insert(stem_id, &mut pool.acquire().await.unwrap())
+            .await
+            .unwrap();
+        leaf = brainace_core::SqlLeaf::into_leaf(
+            &sqlx::query_as("SELECT * FROM leaves WHERE id = ?")
+                .bind(id)
+                .fetch_one(&pool)
+                .await
+                .unwrap(),
+        );
+
+        // Learned yesterday, then reviewed twice today.
+        for (day, rating) in [
+            (9, Rating::Again),
+            (9, Rating::Easy),
+            (0, Rating::Good),
+            (0, Rating::Again),
+        ] {
+            let now = Utc::now() - Duration::days(day);
+            leaf.record_review(user_id, &config, rating, now, &pool)
+                .await
+                .unwrap();
+        }
+    }
+
+    let settings = StudySettings::default();
+    let today = DailyCounts::since(user_id, settings.day_start(Utc::now()), &pool)
+        .await
+        .unwrap();
+    let everything = DailyCounts::since(user_id, start, &pool).await.unwrap();
+
+    assert_eq!(today, DailyCounts { new: 0, review: 4 });
+    assert_eq!(everything.new, 2);
+}
+
+#[tokio::test]
+async fn both_new_directions_count_towards_the_limit() {
+    let pool = common::pool().await;
+    let (user_id, stem_id) = common::garden("alice", &pool).await;
+
+    let mut leaf = Leaf::new("Hund", "dog", Utc::now());
+    leaf.set_bidirectional(true);
+    let id = leaf
+        .insert(stem_id, &mut pool.acquire().await.unwrap())
+        .await
+        .unwrap() as u32;
+
+    let config = Config::default();
+    let now = Utc::now();
+    for direction in [Direction::Forward, Direction::Reverse] {
+        let mut leaf = Leaf::get(id, &pool).await.unwrap().asked(direction);
+        for rating in [Rating::Again, Rating::Good] {
+            leaf.record_review(user_id, &config, rating, now, &pool)
+                .await
+                .unwrap();
+        }
+    }
+
+    let counts = DailyCounts::since(user_id, now - Duration::hours(1), &pool)
+        .await
+        .unwrap();
+
+    assert_eq!(counts.new, 2);
+}
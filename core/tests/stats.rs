@@ -0,0 +1,204 @@
+mod common;
+
+use brainace_core::{
+    review_log::ReviewLog,
+    settings::StudySettings,
+    stats::{
+        forecast, heatmap, leaf_count, review_counts, reviews_per_day, study_days, true_retention,
+        CardStats, DayCount, Streaks,
+    },
+    Card, Config, Leaf, Rating, State,
+};
+use std::collections::BTreeMap;
+
+use chrono::{Duration, NaiveDate, TimeZone, Utc};
+
+fn card(state: State, due_in_days: i64, stability: f64) -> Card {
+    Card {
+        state,
+        due: Utc.with_ymd_and_hms(2024, 3, 10, 12, 0, 0).unwrap() + Duration::days(due_in_days),
+        stability,
+        ..Default::default()
+    }
+}
+
+fn log(state: State, rating: Rating, hour: u32) -> ReviewLog {
+    let previous_card = Card {
+        state,
+        ..Default::default()
+    };
+    let reviewed_at = Utc.with_ymd_and_hms(2024, 3, 10, hour, 0, 0).unwrap();
+
+    ReviewLog::new(1, 1, rating, previous_card, &Card::default(), reviewed_at)
+}
+
+fn counts(series: &[DayCount]) -> Vec<u32> {
+    series.iter().map(|day| day.count).collect()
+}
+
+#[test]
+fn reviews_are_counted_per_study_day() {
+    let settings = StudySettings::default();
+    let today = NaiveDate::from_ymd_opt(2024, 3, 10).unwrap();
+    let logs = [
+        log(State::Review, Rating::Good, 2),
+        log(State::Review, Rating::Good, 10),
+        log(State::New, Rating::Again, 20),
+    ];
+
+    let series = reviews_per_day(&logs, &settings, today, 3);
+
+    assert_eq!(series[0].date, NaiveDate::from_ymd_opt(2024, 3, 8).unwrap());
+    assert_eq!(series[2].date, today);
+    // The day starts at 4, so the review at 2 counts towards the day before.
+    assert_eq!(counts(&series), vec![0, 1, 2]);
+}
+
+#[test]
+fn retention_only_counts_learned_leaves() {
+    let logs = [
+        log(State::Review, Rating::Good, 10),
+        log(State::Review, Rating::Hard, 11),
+        log(State::Review, Rating::Again, 12),
+        log(State::Review, Rating::Easy, 13),
+        log(State::Learning, Rating::Again, 14),
+    ];
+
+    assert_eq!(true_retention(&logs), Some(0.75));
+    assert_eq!(true_retention(&logs[4..]), None);
+}
+
+#[test]
+fn forecast_counts_overdue_leaves_today() {
+    let settings = StudySettings::default();
+    let today = NaiveDate::from_ymd_opt(2024, 3, 10).unwrap();
+    let cards = [
+        card(State::Review, -3, 1.0),
+        card(State::Review, 0, 1.0),
+        card(State::Relearning, 2, 1.0),
+        card(State::Review, 40, 1.0),
+        card(State::New, 0, 0.0),
+    ];
+
+    assert_eq!(
+        counts(&forecast(&cards, &settings, today, 4)),
+        vec![2, 0, 1, 0]
+    );
+}
+
+#[test]
+fn cards_are_broken_down_by_state() {
+    let cards = [
+        card(State::New, 0, 0.0),
+        card(State::New, 0, 0.0),
+        card(State::Learning, 0, 1.0),
+        card(State::Review, 0, 10.0),
+        card(State::Relearning, 0, 4.0),
+    ];
+
+    let stats = CardStats::from_cards(&cards);
+
+    assert_eq!(
+        (stats.new, stats.learning, stats.review, stats.relearning),
+        (2, 1, 1, 1)
+    );
+    assert_eq!(stats.average_stability, Some(5.0));
+    assert_eq!(CardStats::from_cards(&[]).average_stability, None);
+}
+
+fn days(dates: &[u32]) -> BTreeMap<NaiveDate, u32> {
+    dates
+        .iter()
+        .map(|&day| (NaiveDate::from_ymd_opt(2024, 3, day).unwrap(), 1))
+        .collect()
+}
+
+#[test]
+fn streaks_run_over_consecutive_days() {
+    let today = NaiveDate::from_ymd_opt(2024, 3, 10).unwrap();
+
+    let streaks = Streaks::from_days(&days(&[1, 2, 3, 4, 6, 8, 9, 10]), today);
+    assert_eq!(
+        streaks,
+        Streaks {
+            current: 3,
+            longest: 4
+        }
+    );
+
+    // Today's review may still come.
+    let streaks = Streaks::from_days(&days(&[8, 9]), today);
+    assert_eq!(
+        streaks,
+        Streaks {
+            current: 2,
+            longest: 2
+        }
+    );
+
+    let streaks = Streaks::from_days(&days(&[7, 8]), today);
+    assert_eq!(
+        streaks,
+        Streaks {
+            current: 0,
+            longest: 2
+        }
+    );
+}
+
+#[test]
+fn heatmaps_follow_study_days() {
+    let settings = StudySettings {
+        timezone: "Asia/Kolkata".to_string(),
+        ..Default::default()
+    };
+    let reviews = [
+        // 22:15 and 23:45 in Kolkata, on the 9th.
+        (Utc.with_ymd_and_hms(2024, 3, 9, 16, 45, 0).unwrap(), 2),
+        (Utc.with_ymd_and_hms(2024, 3, 9, 18, 15, 0).unwrap(), 1),
+        // 03:45 on the 10th, before the day starts at 4.
+        (Utc.with_ymd_and_hms(2024, 3, 9, 22, 15, 0).unwrap(), 4),
+        // 04:15 on the 10th.
+        (Utc.with_ymd_and_hms(2024, 3, 9, 22, 45, 0).unwrap(), 1),
+    ];
+    let today = NaiveDate::from_ymd_opt(2024, 3, 10).unwrap();
+
+    let days = study_days(&reviews, &settings);
+
+    assert_eq!(counts(&heatmap(&days, today, 3)), vec![0, 7, 1]);
+}
+
+#[tokio::test]
+async fn reviews_are_counted_per_quarter_of_an_hour() {
+    let pool = common::pool().await;
+    let (user_id, stem_id) = common::garden("alice", &pool).await;
+    let leaf_id = common::leaf(stem_id, "Front", &pool).await;
+    let config = Config::default();
+    let start = Utc.with_ymd_and_hms(2024, 3, 10, 12, 0, 0).unwrap();
+
+    let mut reviewed = Leaf::get(leaf_id, &pool).await.unwrap();
+    for minutes in [1, 14, 20, 75] {
+        reviewed
+            .record_review(
+                user_id,
+                &config,
+                Rating::Good,
+                start + Duration::minutes(minutes),
+                &pool,
+            )
+            .await
+            .unwrap();
+    }
+
+    let counts = review_counts(user_id, &pool).await.unwrap();
+
+    assert_eq!(
+        counts,
+        vec![
+            (start, 2),
+            (start + Duration::minutes(15), 1),
+            (start + Duration::minutes(75), 1),
+        ]
+    );
+    assert_eq!(leaf_count(user_id, &pool).await.unwrap(), 1);
+}
@@ -1,4 +1,4 @@
-use brainace_core::Card;
+use brainace_core::Leaf;
 use iced::{
     widget::{column, component, container, horizontal_space, row, text, Component},
     Length,
@@ -10,17 +10,18 @@ use crate::{
 };
 
 pub struct CardView {
-    pub card: Card,
+    pub leaf: Leaf,
+    revealed: bool,
 }
 
 impl CardView {
-    fn new(card: Card) -> Self {
-        Self { card }
+    fn new(leaf: Leaf, revealed: bool) -> Self {
+        Self { leaf, revealed }
     }
 }
 
-pub fn card_view(card: Card) -> CardView {
-    CardView::new(card)
+pub fn card_view(leaf: Leaf, revealed: bool) -> CardView {
+    CardView::new(leaf, revealed)
 }
 
 impl<Message> Component<Message, Renderer> for CardView {
@@ -32,10 +33,11 @@ impl<Message> Component<Message, Renderer> for CardView {
     }
 
     fn view(&self, _state: &Self::State) -> Element<()> {
-        let front = text(self.card.front()).size(25);
-        let back = text(self.card.back()).size(25).style(theme::Text::Accent);
+        let front = text(self.leaf.prompt(self.revealed)).size(25);
+        let back = text(self.leaf.back()).size(25).style(theme::Text::Accent);
 
-        let content: Element<_> = if self.card.revealed {
+        // Cloze leaves are answered on their front, and may have nothing to add on the back.
+        let content: Element<_> = if self.revealed && !self.leaf.back().is_empty() {
             let front_container = container(front)
                 .width(Length::Fill)
                 .center_x()
@@ -66,24 +68,24 @@ impl<'a, Message: 'a> From<CardView> for Element<'a, Message> {
 }
 
 pub struct CardEditView<Message> {
-    card: Card,
+    leaf: Leaf,
     on_event: Box<dyn Fn(CardEvent) -> Message + 'static>,
 }
 
 impl<Message> CardEditView<Message> {
-    fn new(card: Card, on_event: impl Fn(CardEvent) -> Message + 'static) -> Self {
+    fn new(leaf: Leaf, on_event: impl Fn(CardEvent) -> Message + 'static) -> Self {
         Self {
-            card,
+            leaf,
             on_event: Box::new(on_event),
         }
     }
 }
 
 pub fn card_edit_view<Message>(
-    card: Card,
+    leaf: Leaf,
     on_event: impl Fn(CardEvent) -> Message + 'static,
 ) -> CardEditView<Message> {
-    CardEditView::new(card, on_event)
+    CardEditView::new(leaf, on_event)
 }
 
 #[derive(Clone, Debug)]
@@ -94,24 +96,24 @@ pub enum CardEvent {
 }
 
 impl<Message> Component<Message, Renderer> for CardEditView<Message> {
-    type State = ();
+    type State = bool;
     type Event = CardEvent;
 
-    fn update(&mut self, _state: &mut Self::State, event: Self::Event) -> Option<Message> {
+    fn update(&mut self, revealed: &mut Self::State, event: Self::Event) -> Option<Message> {
         match event {
             CardEvent::Toggle => {
-                self.card.revealed = !self.card.revealed;
+                *revealed = !*revealed;
                 None
             }
             CardEvent::Edit | CardEvent::Delete => Some((self.on_event)(event)),
         }
     }
 
-    fn view(&self, _state: &Self::State) -> Element<CardEvent> {
-        let front = text(self.card.front()).size(25);
-        let back = text(self.card.back()).size(25).style(theme::Text::Accent);
+    fn view(&self, revealed: &Self::State) -> Element<CardEvent> {
+        let front = text(self.leaf.prompt(*revealed)).size(25);
+        let back = text(self.leaf.back()).size(25).style(theme::Text::Accent);
 
-        let eye_button = if self.card.revealed {
+        let eye_button = if *revealed {
             icon_btn(icon_eye(20.0), Some(CardEvent::Toggle))
         } else {
             icon_btn(icon_eye_off(20.0), Some(CardEvent::Toggle))
@@ -129,7 +131,7 @@ impl<Message> Component<Message, Renderer> for CardEditView<Message> {
         ]
         .spacing(5);
 
-        let content: Element<_> = if self.card.revealed {
+        let content: Element<_> = if *revealed {
             let front_container = container(front_with_controls)
                 .width(Length::Fill)
                 .center_x()
@@ -1,4 +1,4 @@
-use brainace_core::Card;
+use brainace_core::Leaf;
 use iced::{
     font::Weight,
     widget::{column, component, container, row, text, text_input, Component},
@@ -19,12 +19,12 @@ pub struct CardEditor<Message> {
 
 impl<Message> CardEditor<Message> {
     fn new(
-        card: Option<Card>,
+        leaf: Option<Leaf>,
         on_cancel: impl Fn() -> Message + 'static,
         on_confirm: impl Fn(&str, &str) -> Message + 'static,
     ) -> Self {
-        let (front, back) = match card {
-            Some(card) => (card.front(), card.back()),
+        let (front, back) = match leaf {
+            Some(leaf) => (leaf.front(), leaf.back()),
             None => (String::new(), String::new()),
         };
 
@@ -38,11 +38,11 @@ impl<Message> CardEditor<Message> {
 }
 
 pub fn card_editor<Message>(
-    card: Option<Card>,
+    leaf: Option<Leaf>,
     on_cancel: impl Fn() -> Message + 'static,
     on_confirm: impl Fn(&str, &str) -> Message + 'static,
 ) -> CardEditor<Message> {
-    CardEditor::new(card, on_cancel, on_confirm)
+    CardEditor::new(leaf, on_cancel, on_confirm)
 }
 
 #[derive(Clone)]
@@ -1,4 +1,5 @@
-use brainace_core::{Card, Deck};
+use brainace_core::{Deck, Leaf};
+use chrono::Utc;
 use iced::{
     widget::{column, component, horizontal_space, row, text, text_input, Component},
     Alignment, Length,
@@ -50,9 +51,9 @@ impl<Message> Component<Message, Renderer> for DeckView<Message> {
 
     fn update(&mut self, _state: &mut Self::State, event: DeckEvent) -> Option<Message> {
         match event {
-            DeckEvent::NewCard(i) => {
-                let card = Card::new("", "");
-                self.deck.cards.push(card);
+            DeckEvent::NewCard(_) => {
+                let leaf = Leaf::new("", "", Utc::now());
+                self.deck.leaves.push(leaf);
 
                 None
             }
@@ -71,7 +72,7 @@ impl<Message> Component<Message, Renderer> for DeckView<Message> {
             DeckEvent::Review => Some((self.on_review)()),
             DeckEvent::CardEvent(i, card_event) => match card_event {
                 CardEvent::Delete => {
-                    self.deck.cards.remove(i);
+                    self.deck.leaves.remove(i);
                     None
                 }
                 _ => None,
@@ -92,7 +93,7 @@ impl<Message> Component<Message, Renderer> for DeckView<Message> {
 
         let plus_button = action(
             icon_plus(30.0),
-            Some(DeckEvent::NewCard(self.deck.cards.len())),
+            Some(DeckEvent::NewCard(self.deck.leaves.len())),
         );
         let pencil_button = action(icon_pencil(30.0), Some(DeckEvent::EditName));
 
@@ -109,11 +110,11 @@ impl<Message> Component<Message, Renderer> for DeckView<Message> {
 
         let cards: Vec<Element<_>> = self
             .deck
-            .cards
+            .leaves
             .iter()
             .enumerate()
-            .map(|(i, card)| {
-                card_edit_view(card.clone(), move |card_event| {
+            .map(|(i, leaf)| {
+                card_edit_view(leaf.clone(), move |card_event| {
                     DeckEvent::CardEvent(i, card_event)
                 })
                 .into()
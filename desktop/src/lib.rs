@@ -1,5 +1,4 @@
 mod components;
-mod reviewing;
 mod theme;
 mod widget;
 
@@ -7,7 +6,7 @@ use std::io;
 use std::path::PathBuf;
 use std::sync::Arc;
 
-use brainace_core::Deck;
+use brainace_core::{config::format_interval, deck_file::DeckFile, Deck, Rating};
 use components::card::{card_view, CardEvent};
 use components::card_editor::card_editor;
 use components::deck::{deck_view, DeckEvent, DeckView};
@@ -28,10 +27,11 @@ use widget::modal::Modal;
 pub struct App {
     show_modal: bool,
     mode: Mode,
-    fsrs: brainace_core::Config,
+    config: brainace_core::Config,
     deck_view: Option<DeckView<Message>>,
     editing_id: usize,
     reviewing_id: usize,
+    revealed: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -48,7 +48,7 @@ pub enum Message {
     DeckEvent(DeckEvent),
     Skip,
     Reveal,
-    Rate(u32),
+    Rate(Rating),
     Continue,
     Close,
     Settings,
@@ -67,7 +67,7 @@ impl<'a> Application for App {
     type Flags = ();
 
     fn new(_flags: Self::Flags) -> (Self, Command<Message>) {
-        let fsrs = brainace_core::Config::default();
+        let config = brainace_core::Config::default();
 
         let batch = [window::change_mode(
             window::Id::MAIN,
@@ -78,10 +78,11 @@ impl<'a> Application for App {
             Self {
                 show_modal: false,
                 mode: Mode::Managing,
-                fsrs,
+                config,
                 deck_view: None,
                 editing_id: 0,
                 reviewing_id: 0,
+                revealed: false,
             },
             Command::batch(batch),
         )
@@ -107,13 +108,9 @@ impl<'a> Application for App {
                         self.mode = mode;
                     }
                     Mode::Reviewing => {
-                        if let Some(deck_view) = &mut self.deck_view {
-                            deck_view
-                                .deck
-                                .cards
-                                .iter_mut()
-                                .for_each(|card| card.set_back(""));
+                        if self.deck_view.is_some() {
                             self.reviewing_id = 0;
+                            self.revealed = false;
 
                             self.mode = mode;
                         }
@@ -124,7 +121,9 @@ impl<'a> Application for App {
             }
             Message::Open => Command::perform(pick_file(), Message::DeckLoaded),
             Message::Save => {
-                let text = ron::to_string(&self.deck_view.as_ref().unwrap().deck).unwrap();
+                let text = DeckFile::from(&self.deck_view.as_ref().unwrap().deck)
+                    .to_ron()
+                    .unwrap();
 
                 Command::perform(
                     save_file(Some("mathematical_constants.ron".into()), text),
@@ -137,16 +136,31 @@ impl<'a> Application for App {
             }
             Message::ConfirmEdit(front, back) => {
                 if let Some(deck_view) = &mut self.deck_view {
-                    deck_view.deck.cards[self.editing_id].set_front(&front);
-                    deck_view.deck.cards[self.editing_id].set_back(&back);
+                    deck_view.deck.leaves[self.editing_id].set_front(&front);
+                    deck_view.deck.leaves[self.editing_id].set_back(&back);
                 }
 
                 self.show_modal = false;
                 Command::none()
             }
             Message::DeckLoaded(Ok(content)) => {
-                if let Some(deck_view) = &mut self.deck_view {
-                    deck_view.deck = ron::from_str(content.as_str()).unwrap();
+                // A file that isn't a deck file leaves the open deck as it is, as a failed read does.
+                let Ok(file) = DeckFile::parse(content.as_str()) else {
+                    return Command::none();
+                };
+                let deck = file
+                    .into_decks(Utc::now())
+                    .into_iter()
+                    .next()
+                    .unwrap_or_default();
+
+                match &mut self.deck_view {
+                    Some(view) => view.deck = deck,
+                    None => {
+                        self.deck_view = Some(deck_view(deck, || {
+                            Message::DeckEvent(DeckEvent::Review)
+                        }))
+                    }
                 }
 
                 Command::none()
@@ -172,29 +186,25 @@ impl<'a> Application for App {
             Message::Skip => {
                 if let Some(deck_view) = &self.deck_view {
                     self.reviewing_id =
-                        (self.reviewing_id + 1).clamp(0, deck_view.deck.cards.len());
+                        (self.reviewing_id + 1).clamp(0, deck_view.deck.leaves.len());
+                    self.revealed = false;
                 }
 
                 Command::none()
             }
             Message::Reveal => {
-                if let Some(deck_view) = &mut self.deck_view {
-                    if let Some(card) = deck_view.deck.cards.get_mut(self.reviewing_id) {
-                        if card.revealed {
-                            self.reviewing_id =
-                                (self.reviewing_id + 1).clamp(0, deck_view.deck.cards.len());
-                        }
-                    }
-                }
-
+                self.revealed = true;
                 Command::none()
             }
             Message::Rate(rating) => {
                 if let Some(deck_view) = &mut self.deck_view {
-                    deck_view.deck.cards[self.reviewing_id].review(rating, Utc::now());
+                    deck_view
+                        .deck
+                        .review(self.reviewing_id, &self.config, rating, Utc::now());
 
                     self.reviewing_id =
-                        (self.reviewing_id + 1).clamp(0, deck_view.deck.cards.len());
+                        (self.reviewing_id + 1).clamp(0, deck_view.deck.leaves.len());
+                    self.revealed = false;
                 }
 
                 Command::none()
@@ -210,6 +220,8 @@ impl<'a> Application for App {
             Mode::Reviewing => review_page(
                 self.deck_view.as_ref().unwrap().deck.clone(),
                 self.reviewing_id,
+                self.revealed,
+                &self.config,
             ),
         };
 
@@ -217,7 +229,7 @@ impl<'a> Application for App {
             || "".into(),
             |deck_view| {
                 card_editor(
-                    Some(deck_view.deck.cards[self.editing_id].clone()),
+                    deck_view.deck.leaves.get(self.editing_id).cloned(),
                     || Message::CancelEdit,
                     |front, back| Message::ConfirmEdit(front.to_string(), back.to_string()),
                 )
@@ -273,16 +285,21 @@ fn manage_page<'a>(maybe_deck: Option<Deck>) -> Element<'a, Message> {
         .into()
 }
 
-fn review_page<'a>(deck: Deck, id: usize) -> Element<'a, Message> {
+fn review_page<'a>(
+    deck: Deck,
+    id: usize,
+    revealed: bool,
+    config: &brainace_core::Config,
+) -> Element<'a, Message> {
     let cancel_icon = action(icon_cancel(25.0), Some(Message::Close));
     let cog_icon = action(icon_cog(25.0), Some(Message::Settings));
 
-    let progress_bar = progress_bar(0.0..=(deck.cards.len() as f32), id as f32).height(15);
+    let progress_bar = progress_bar(0.0..=(deck.leaves.len() as f32), id as f32).height(15);
 
     let mut nunito_bold = Font::with_name("nunito");
     nunito_bold.weight = Weight::Semibold;
 
-    let progress = text(format!("{} / {}", id, deck.cards.len()))
+    let progress = text(format!("{} / {}", id, deck.leaves.len()))
         .font(nunito_bold)
         .size(20)
         .style(theme::Text::Secondary);
@@ -296,13 +313,13 @@ fn review_page<'a>(deck: Deck, id: usize) -> Element<'a, Message> {
     .height(150)
     .center_y();
 
-    let main_content: Element<_> = if id == deck.cards.len() {
+    let main_content: Element<_> = if id == deck.leaves.len() {
         container(text("Congratulations!").size(50))
             .width(Length::Fill)
             .center_x()
             .into()
     } else {
-        card_view(deck.cards[id].clone()).into()
+        card_view(deck.leaves[id].clone(), revealed).into()
     };
 
     let main = container(main_content)
@@ -311,7 +328,7 @@ fn review_page<'a>(deck: Deck, id: usize) -> Element<'a, Message> {
         .center_y()
         .padding([0, 125]);
 
-    let footer_content: Element<_> = if id == deck.cards.len() {
+    let footer_content: Element<_> = if id == deck.leaves.len() {
         let continue_button = action_btn(
             "CONTINUE",
             theme::Button::Default,
@@ -319,11 +336,16 @@ fn review_page<'a>(deck: Deck, id: usize) -> Element<'a, Message> {
         );
 
         continue_button
-    } else if deck.cards[id].revealed {
-        let again_button = border_action_btn("AGAIN", ROSE_500, Message::Rate(1));
-        let hard_button = border_action_btn("HARD", YELLOW_500, Message::Rate(2));
-        let good_button = border_action_btn("GOOD", CYAN_500, Message::Rate(3));
-        let easy_button = border_action_btn("EASY", GREEN_500, Message::Rate(4));
+    } else if revealed {
+        let [again, hard, good, easy] = deck.leaves[id]
+            .intervals(config, Utc::now())
+            .map(format_interval);
+
+        let again_button =
+            border_action_btn("AGAIN", &again, ROSE_500, Message::Rate(Rating::Again));
+        let hard_button = border_action_btn("HARD", &hard, YELLOW_500, Message::Rate(Rating::Hard));
+        let good_button = border_action_btn("GOOD", &good, CYAN_500, Message::Rate(Rating::Good));
+        let easy_button = border_action_btn("EASY", &easy, GREEN_500, Message::Rate(Rating::Easy));
 
         container(row![again_button, hard_button, good_button, easy_button].spacing(15)).into()
     } else {
@@ -432,13 +454,20 @@ fn icon_btn<'a, Msg: 'a + Clone>(
 
 fn border_action_btn<'a, Msg: 'a + Clone>(
     button_text: &str,
+    interval: &str,
     color: Color,
     on_press: Msg,
 ) -> Element<'a, Msg> {
     let mut nunito_bold = Font::with_name("nunito");
     nunito_bold.weight = Weight::Semibold;
 
-    button(text(button_text).font(nunito_bold).size(25))
+    let content = column![
+        text(interval).size(15).style(theme::Text::Secondary),
+        text(button_text).font(nunito_bold).size(25)
+    ]
+    .align_items(Alignment::Center);
+
+    button(content)
         .on_press(on_press)
         .padding([10, 50])
         .style(theme::Button::BorderedAction(color))
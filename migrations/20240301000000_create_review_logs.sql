@@ -0,0 +1,16 @@
+CREATE TABLE IF NOT EXISTS review_logs (
+    id             INTEGER PRIMARY KEY AUTOINCREMENT,
+    leaf_id        INTEGER NOT NULL,
+    user_id        INTEGER NOT NULL,
+    rating         INTEGER NOT NULL,
+    state          INTEGER NOT NULL,
+    elapsed_days   INTEGER NOT NULL,
+    scheduled_days INTEGER NOT NULL,
+    previous_card  JSONB NOT NULL,
+    reviewed_at    TIMESTAMP NOT NULL,
+    FOREIGN KEY (leaf_id) REFERENCES leaves(id) ON DELETE CASCADE,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS review_logs_user ON review_logs (user_id, reviewed_at);
+CREATE INDEX IF NOT EXISTS review_logs_leaf ON review_logs (leaf_id, reviewed_at);
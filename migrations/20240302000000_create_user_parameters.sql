@@ -0,0 +1,7 @@
+CREATE TABLE IF NOT EXISTS user_parameters (
+    user_id      INTEGER NOT NULL PRIMARY KEY,
+    weights      JSONB NOT NULL,
+    review_count INTEGER NOT NULL,
+    optimized_at TIMESTAMP NOT NULL,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
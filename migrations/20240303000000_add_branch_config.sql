@@ -0,0 +1,3 @@
+ALTER TABLE branches ADD COLUMN desired_retention REAL NOT NULL DEFAULT 0.9;
+ALTER TABLE branches ADD COLUMN maximum_interval INTEGER NOT NULL DEFAULT 36500;
+ALTER TABLE branches ADD COLUMN learning_steps TEXT NOT NULL DEFAULT '1m 10m';
@@ -0,0 +1,4 @@
+ALTER TABLE leaves ADD COLUMN due TEXT GENERATED ALWAYS AS (datetime(json_extract(card, '$.due'))) VIRTUAL;
+ALTER TABLE leaves ADD COLUMN state TEXT GENERATED ALWAYS AS (json_extract(card, '$.state')) VIRTUAL;
+
+CREATE INDEX IF NOT EXISTS leaves_due ON leaves (state, due);
@@ -0,0 +1,8 @@
+CREATE TABLE IF NOT EXISTS user_settings (
+    user_id         INTEGER NOT NULL PRIMARY KEY,
+    new_per_day     INTEGER NOT NULL DEFAULT 20,
+    reviews_per_day INTEGER NOT NULL DEFAULT 200,
+    day_starts_at   INTEGER NOT NULL DEFAULT 4,
+    timezone        TEXT NOT NULL DEFAULT 'UTC',
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
@@ -0,0 +1 @@
+ALTER TABLE leaves ADD COLUMN cloze INTEGER;
@@ -0,0 +1,15 @@
+ALTER TABLE leaves ADD COLUMN reverse_card JSONB;
+ALTER TABLE leaves ADD COLUMN reverse_due TEXT GENERATED ALWAYS AS (datetime(json_extract(reverse_card, '$.due'))) VIRTUAL;
+ALTER TABLE leaves ADD COLUMN reverse_state TEXT GENERATED ALWAYS AS (json_extract(reverse_card, '$.state')) VIRTUAL;
+
+CREATE INDEX IF NOT EXISTS leaves_reverse_due ON leaves (reverse_state, reverse_due);
+
+ALTER TABLE review_logs ADD COLUMN reversed BOOLEAN NOT NULL DEFAULT FALSE;
+
+CREATE VIEW IF NOT EXISTS leaf_sides AS
+    SELECT id, stem_id, front, back, card, cloze, reverse_card, created_at, FALSE AS reversed, card AS side_card, state, due
+        FROM leaves
+    UNION ALL
+    SELECT id, stem_id, front, back, card, cloze, reverse_card, created_at, TRUE AS reversed, reverse_card AS side_card, reverse_state AS state, reverse_due AS due
+        FROM leaves
+        WHERE reverse_card IS NOT NULL;
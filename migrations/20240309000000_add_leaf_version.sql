@@ -0,0 +1,11 @@
+ALTER TABLE leaves ADD COLUMN version INTEGER NOT NULL DEFAULT 0;
+
+DROP VIEW IF EXISTS leaf_sides;
+
+CREATE VIEW leaf_sides AS
+    SELECT id, stem_id, front, back, card, cloze, reverse_card, created_at, version, FALSE AS reversed, card AS side_card, state, due
+        FROM leaves
+    UNION ALL
+    SELECT id, stem_id, front, back, card, cloze, reverse_card, created_at, version, TRUE AS reversed, reverse_card AS side_card, reverse_state AS state, reverse_due AS due
+        FROM leaves
+        WHERE reverse_card IS NOT NULL;
@@ -0,0 +1,23 @@
+ALTER TABLE leaves ADD COLUMN note TEXT;
+
+-- The clozes grown before notes were kept share their stem, front and creation time.
+UPDATE leaves SET note = (
+    SELECT 'leaf-' || MIN(grown.id) FROM leaves AS grown
+        WHERE grown.stem_id = leaves.stem_id
+            AND grown.front = leaves.front
+            AND grown.created_at = leaves.created_at
+            AND grown.cloze IS NOT NULL
+)
+    WHERE cloze IS NOT NULL;
+
+CREATE INDEX IF NOT EXISTS leaves_note ON leaves (stem_id, note);
+
+DROP VIEW IF EXISTS leaf_sides;
+
+CREATE VIEW leaf_sides AS
+    SELECT id, stem_id, front, back, card, cloze, note, reverse_card, created_at, version, FALSE AS reversed, card AS side_card, state, due
+        FROM leaves
+    UNION ALL
+    SELECT id, stem_id, front, back, card, cloze, note, reverse_card, created_at, version, TRUE AS reversed, reverse_card AS side_card, reverse_state AS state, reverse_due AS due
+        FROM leaves
+        WHERE reverse_card IS NOT NULL;
@@ -0,0 +1,26 @@
+// Uploads the file picked in `input` and inserts the Markdown referring to it in the field with id
+// `target`, where the cursor is.
+window.attachMedia = async (input, target) => {
+  const file = input.files[0];
+  if (!file) {
+    return;
+  }
+
+  const data = new FormData();
+  data.append("file", file);
+  const response = await fetch("/media", { method: "POST", body: data });
+  const text = await response.text();
+
+  if (response.ok) {
+    const field = document.getElementById(target);
+    field.setRangeText(text, field.selectionStart, field.selectionEnd, "end");
+    // Lets the editor know, so its preview shows the attachment.
+    field.dispatchEvent(new Event("input", { bubbles: true }));
+    field.focus();
+    input.value = "";
+    input.setCustomValidity("");
+  } else {
+    input.setCustomValidity(text);
+    input.reportValidity();
+  }
+};
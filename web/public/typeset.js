@@ -0,0 +1,16 @@
+// Typesets the math spans of rendered leaves, once KaTeX has loaded.
+window.typesetMath = (element) => {
+  if (!window.katex) {
+    return;
+  }
+
+  for (const math of element.querySelectorAll(".math:not(.katex-typeset)")) {
+    katex.render(math.textContent, math, {
+      displayMode: math.classList.contains("math-display"),
+      throwOnError: false,
+    });
+    math.classList.add("katex-typeset");
+  }
+};
+
+window.addEventListener("load", () => window.typesetMath(document.body));
@@ -1,5 +1,5 @@
 use leptos::{component, create_resource, create_server_action, view, IntoView, SignalGet};
-use leptos_meta::{provide_meta_context, Body, Html, Link, Stylesheet, Title};
+use leptos_meta::{provide_meta_context, Body, Html, Link, Script, Stylesheet, Title};
 use leptos_router::{Outlet, Route, Router, Routes, A};
 
 use crate::{
@@ -8,14 +8,17 @@ use crate::{
         leaf::{LeafDetails, NoLeaf},
         stem::{NoStem, Stem},
     },
-    review::Review,
+    profile::Profile,
+    review::{BranchReview, Review, StemReview},
+    settings::Settings,
+    stats::Stats,
     ui::{SideBar, SideBarItem, SideBarItems, SideBarSeparator, SideContent},
     users::{get_user, Login, LoginSection, Logout, Signup},
 };
 
 #[cfg(feature = "ssr")]
 pub mod ssr {
-    use brainace_core::auth::AuthSession;
+    use brainace_core::auth::{AccessError, AuthSession, User};
     use leptos::{use_context, ServerFnError};
     use sqlx::SqlitePool;
 
@@ -28,6 +31,12 @@ pub mod ssr {
         use_context::<AuthSession>()
             .ok_or_else(|| ServerFnError::ServerError("Auth session missing.".into()))
     }
+
+    pub fn user() -> Result<User, ServerFnError> {
+        auth()?
+            .current_user
+            .ok_or_else(|| AccessError::Unauthorized.into())
+    }
 }
 
 #[component]
@@ -53,6 +62,20 @@ pub fn App() -> impl IntoView {
         <Title text="Brainace"/>
         <Link rel="shortcut icon" type_="image/ico" href="/Brainace_Icon_Dark.ico"/>
         <Stylesheet id="leptos" href="/pkg/brainace_web.css"/>
+        <Link
+            id="katex"
+            rel="stylesheet"
+            href="https://cdn.jsdelivr.net/npm/katex@0.16.9/dist/katex.min.css"
+            integrity="sha384-n8MVd4RsNIU0tAv4ct0nTaAbDJwPJzDEaqSD1odI+WdtXRGWt2kTvGFasHpSy3SV"
+            crossorigin="anonymous"
+        />
+        <Script
+            src="https://cdn.jsdelivr.net/npm/katex@0.16.9/dist/katex.min.js"
+            integrity="sha384-XjKyOOlGwcjNTAIQHIpgOno0Hl1YQqzUOEleOLALmuqehneUG+vnGctmUb0ZY0l8"
+            crossorigin="anonymous"
+        />
+        <Script src="/typeset.js"/>
+        <Script src="/media.js"/>
         <Html lang="en" class="h-full"/>
         <Body class="h-full flex flex-col bg-secondary-870"/>
         <Router>
@@ -76,6 +99,16 @@ pub fn App() -> impl IntoView {
                                         icon=icondata::FaBrainSolid
                                         text="REVIEW ALL"
                                     />
+                                    <SideBarItem
+                                        href="/stats"
+                                        icon=icondata::FaChartSimpleSolid
+                                        text="STATISTICS"
+                                    />
+                                    <SideBarItem
+                                        href="/settings"
+                                        icon=icondata::FaGearSolid
+                                        text="SETTINGS"
+                                    />
                                 </SideBarItems>
                                 <SideBarSeparator/>
                                 <LoginSection user logout/>
@@ -96,10 +129,15 @@ pub fn App() -> impl IntoView {
                     <Route path="/stem/:id" view=Stem/>
                     <Route path="/leaf" view=NoLeaf/>
                     <Route path="/leaf/:id" view=LeafDetails/>
+                    <Route path="/profile" view=Profile/>
+                    <Route path="/stats" view=Stats/>
+                    <Route path="/settings" view=Settings/>
                     <Route path="/login" view=move || view! { <Login action=login/> }/>
                     <Route path="/signup" view=move || view! { <Signup action=signup/> }/>
                 </Route>
-                <Route path="/review" view=Review/>
+                <Route path="/review" view=|| view! { <Review/> }/>
+                <Route path="/branch/:id/review" view=BranchReview/>
+                <Route path="/stem/:id/review" view=StemReview/>
             </Routes>
         </Router>
     }
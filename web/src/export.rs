@@ -0,0 +1,127 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+};
+use brainace_core::{
+    auth::{AccessError, AuthSession},
+    csv_file::write_leaves,
+    deck_file::DeckFile,
+    Deck,
+};
+use serde::Deserialize;
+use sqlx::SqlitePool;
+
+#[derive(Deserialize, Default, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    #[default]
+    Ron,
+    Json,
+    Csv,
+    Tsv,
+}
+
+#[derive(Deserialize)]
+pub struct ExportParams {
+    #[serde(default)]
+    format: Format,
+}
+
+pub async fn export_branch(
+    State(pool): State<SqlitePool>,
+    auth_session: AuthSession,
+    Path(id): Path<u32>,
+    Query(params): Query<ExportParams>,
+) -> Result<Response, StatusCode> {
+    let user = auth_session.current_user.ok_or(StatusCode::UNAUTHORIZED)?;
+    user.authorize_branch(id, &pool)
+        .await
+        .map_err(access_status)?;
+
+    // A spreadsheet holds a single stem.
+    if matches!(params.format, Format::Csv | Format::Tsv) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let file = DeckFile::from_branch(id, &pool)
+        .await
+        .ok_or(StatusCode::INTERNAL_SERVER_ERROR)?;
+    let name = file.branch.clone().unwrap_or_default();
+
+    deck_file_response(&file, &name, params.format)
+}
+
+pub async fn export_stem(
+    State(pool): State<SqlitePool>,
+    auth_session: AuthSession,
+    Path(id): Path<u32>,
+    Query(params): Query<ExportParams>,
+) -> Result<Response, StatusCode> {
+    let user = auth_session.current_user.ok_or(StatusCode::UNAUTHORIZED)?;
+    user.authorize_stem(id, &pool)
+        .await
+        .map_err(access_status)?;
+
+    if let Format::Csv | Format::Tsv = params.format {
+        let deck = Deck::get(id.into(), &pool)
+            .await
+            .ok_or(StatusCode::INTERNAL_SERVER_ERROR)?;
+
+        let (delimiter, extension, content_type) = match params.format {
+            Format::Tsv => (b'\t', "tsv", "text/tab-separated-values; charset=utf-8"),
+            _ => (b',', "csv", "text/csv; charset=utf-8"),
+        };
+        let content =
+            write_leaves(&deck.leaves, delimiter).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+        return Ok(attachment(content, &deck.name, extension, content_type));
+    }
+
+    let file = DeckFile::from_stem(id, &pool)
+        .await
+        .ok_or(StatusCode::INTERNAL_SERVER_ERROR)?;
+    let name = file
+        .decks
+        .first()
+        .map(|deck| deck.name.clone())
+        .unwrap_or_default();
+
+    deck_file_response(&file, &name, params.format)
+}
+
+fn deck_file_response(file: &DeckFile, name: &str, format: Format) -> Result<Response, StatusCode> {
+    let (content, extension, content_type) = match format {
+        Format::Json => (file.to_json(), "json", "application/json"),
+        _ => (file.to_ron(), "ron", "text/plain; charset=utf-8"),
+    };
+    let content = content.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(attachment(content, name, extension, content_type))
+}
+
+fn attachment(content: String, name: &str, extension: &str, content_type: &str) -> Response {
+    let filename: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    let disposition = format!("attachment; filename=\"{filename}.{extension}\"");
+
+    (
+        [
+            (header::CONTENT_TYPE, content_type.to_string()),
+            (header::CONTENT_DISPOSITION, disposition),
+        ],
+        content,
+    )
+        .into_response()
+}
+
+pub(crate) fn access_status(error: AccessError) -> StatusCode {
+    match error {
+        AccessError::Unauthorized => StatusCode::UNAUTHORIZED,
+        AccessError::Forbidden => StatusCode::FORBIDDEN,
+        AccessError::NotFound => StatusCode::NOT_FOUND,
+        AccessError::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
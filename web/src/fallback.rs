@@ -1,19 +1,32 @@
-use crate::{error_template::ErrorTemplate, errors::AppError};
+use crate::{error_template::ErrorTemplate, errors::AppError, media::serve_media};
 use axum::{
     body::Body,
     extract::State,
     http::{Request, Response, StatusCode, Uri},
     response::{IntoResponse, Response as AxumResponse},
 };
+use brainace_core::auth::AuthSession;
 use leptos::{view, Errors, LeptosOptions};
+use sqlx::SqlitePool;
+use std::path::PathBuf;
 use tower::ServiceExt;
 use tower_http::services::ServeDir;
 
 pub async fn file_and_error_handler(
     uri: Uri,
     State(options): State<LeptosOptions>,
+    State(pool): State<SqlitePool>,
+    State(media_dir): State<PathBuf>,
+    auth_session: AuthSession,
     req: Request<Body>,
 ) -> AxumResponse {
+    if let Some(file_name) = uri.path().strip_prefix("/media/") {
+        return match serve_media(file_name, auth_session, &pool, &media_dir).await {
+            Ok(res) => res,
+            Err(status) => status.into_response(),
+        };
+    }
+
     let root = options.site_root.clone();
     let res = get_static_file(uri.clone(), &root).await.unwrap();
 
@@ -1,24 +1,34 @@
 use crate::{
     error_template::ErrorTemplate,
     garden::stem::{AddStem, Stems},
-    ui::{Card, ControlAction, ControlBtn, Controls, FormH1, FormInput, FormSubmit, Modal},
+    ui::{
+        Card, ControlA, ControlAction, ControlBtn, ControlLink, Controls, CountBadges, FormH1,
+        FormInput, FormSubmit, Modal,
+    },
     users::get_user,
 };
-use brainace_core::Branch;
+use brainace_core::{queue::LeafCounts, Branch};
 use leptos::{
-    component, create_resource, create_server_action, create_server_multi_action, create_signal,
-    server, view, CollectView, ErrorBoundary, IntoView, Params, ServerFnError, SignalGet,
+    component, create_action, create_effect, create_resource, create_server_action,
+    create_server_multi_action, create_signal,
+    ev::SubmitEvent,
+    event_target, server,
+    server_fn::codec::{MultipartData, MultipartFormData},
+    view, CollectView, ErrorBoundary, IntoView, Params, ServerFnError, Signal, SignalGet,
     SignalUpdate, SignalWith, Transition,
 };
 use leptos_icons::Icon;
-use leptos_router::{use_navigate, use_params, MultiActionForm, Params, A};
+use leptos_router::{use_navigate, use_params, ActionForm, MultiActionForm, Params, A};
+use std::collections::HashMap;
+use web_sys::{FormData, HtmlFormElement};
 
 #[server(GetBranch, "/api")]
 pub async fn get_branch(id: u32) -> Result<Branch, ServerFnError> {
-    use crate::app::ssr::pool;
+    use crate::app::ssr::{pool, user};
     use brainace_core::SqlBranch;
 
     let pool = pool()?;
+    user()?.authorize_branch(id, &pool).await?;
 
     Ok(
         sqlx::query_as::<_, SqlBranch>("SELECT * FROM branches WHERE id = ?")
@@ -55,6 +65,22 @@ pub async fn get_branches() -> Result<Vec<Branch>, ServerFnError> {
     .await)
 }
 
+/// Due, new and total leaves of each of the user's branches.
+#[server(GetBranchCounts, "/api")]
+pub async fn get_branch_counts() -> Result<HashMap<u32, LeafCounts>, ServerFnError> {
+    use crate::app::ssr::{pool, user};
+    use brainace_core::settings::StudySettings;
+    use chrono::Utc;
+
+    let pool = pool()?;
+    let user = user()?;
+
+    let settings = StudySettings::for_user(user.id, &pool).await?;
+    let now = Utc::now();
+
+    Ok(LeafCounts::per_branch(user.id, now, settings.day_end(now), &pool).await?)
+}
+
 #[server(AddBranch, "/api")]
 pub async fn add_branch(name: String) -> Result<(), ServerFnError> {
     use crate::app::ssr::pool;
@@ -77,11 +103,40 @@ pub async fn add_branch(name: String) -> Result<(), ServerFnError> {
     )
 }
 
+#[server(name = ImportAnki, prefix = "/api", input = MultipartFormData)]
+pub async fn import_anki(data: MultipartData) -> Result<(), ServerFnError> {
+    use crate::app::ssr::{pool, user};
+    use brainace_core::{anki::read_apkg, deck_file::DeckFile};
+    use chrono::Utc;
+
+    let user = user()?;
+    let pool = pool()?;
+
+    let mut data = data
+        .into_inner()
+        .ok_or_else(|| ServerFnError::new("Missing form data."))?;
+
+    let mut package = None;
+    while let Some(field) = data.next_field().await? {
+        if field.name() == Some("package") {
+            package = Some(field.bytes().await?);
+        }
+    }
+
+    let package = package.ok_or_else(|| ServerFnError::new("Missing Anki package."))?;
+
+    let files = read_apkg(&package).await?;
+    DeckFile::insert_all(&files, user.id, Utc::now(), &pool).await?;
+
+    Ok(())
+}
+
 #[server(EditBranch, "/api")]
 pub async fn edit_branch(id: u32, name: String) -> Result<(), ServerFnError> {
-    use crate::app::ssr::pool;
+    use crate::app::ssr::{pool, user};
 
     let pool = pool()?;
+    user()?.authorize_branch(id, &pool).await?;
 
     Ok(sqlx::query("UPDATE branches SET name = $2 WHERE id = $1")
         .bind(id)
@@ -91,11 +146,30 @@ pub async fn edit_branch(id: u32, name: String) -> Result<(), ServerFnError> {
         .map(|_| ())?)
 }
 
+#[server(ConfigureBranch, "/api")]
+pub async fn configure_branch(
+    id: u32,
+    desired_retention: String,
+    maximum_interval: String,
+    learning_steps: String,
+) -> Result<(), ServerFnError> {
+    use crate::app::ssr::{pool, user};
+    use brainace_core::config::BranchConfig;
+
+    let pool = pool()?;
+    user()?.authorize_branch(id, &pool).await?;
+
+    let config = BranchConfig::parse(&desired_retention, &maximum_interval, &learning_steps)?;
+
+    Ok(config.update(id, &pool).await?)
+}
+
 #[server(DeleteBranch, "/api")]
 pub async fn delete_branch(id: u32) -> Result<(), ServerFnError> {
-    use crate::app::ssr::pool;
+    use crate::app::ssr::{pool, user};
 
     let pool = pool()?;
+    user()?.authorize_branch(id, &pool).await?;
 
     Ok(sqlx::query("DELETE FROM branches WHERE id = $1")
         .bind(id)
@@ -112,14 +186,27 @@ struct BranchParams {
 #[component]
 pub fn Branches() -> impl IntoView {
     let (show_modal, set_show_modal) = create_signal(false);
+    let (importing, set_importing) = create_signal(false);
 
     let add_branch = create_server_multi_action::<AddBranch>();
     let submissions = add_branch.submissions();
+    let import_package = create_action(|data: &FormData| import_anki(data.clone().into()));
+
+    // The modal stays open when an import fails, to show why.
+    create_effect(move |_| {
+        if let Some(Ok(())) = import_package.value().get() {
+            set_importing.update(|x| *x = false);
+        }
+    });
 
     let branches = create_resource(
-        move || (add_branch.version().get()),
+        move || (add_branch.version().get(), import_package.version().get()),
         move |_| get_branches(),
     );
+    let counts = create_resource(
+        move || (add_branch.version().get(), import_package.version().get()),
+        move |_| get_branch_counts(),
+    );
 
     view! {
         <Transition fallback=move || view! { <p>"Loading..."</p> }>
@@ -146,9 +233,17 @@ pub fn Branches() -> impl IntoView {
                                             branches
                                                 .into_iter()
                                                 .map(move |branch| {
+                                                    let id = branch.id();
+                                                    let counts = Signal::derive(move || {
+                                                        counts
+                                                            .get()
+                                                            .and_then(Result::ok)
+                                                            .and_then(|counts| counts.get(&id).copied())
+                                                            .unwrap_or_default()
+                                                    });
                                                     view! {
                                                         <li>
-                                                            <BranchOverview branch=branch/>
+                                                            <BranchOverview branch=branch counts/>
                                                         </li>
                                                     }
                                                 })
@@ -183,6 +278,14 @@ pub fn Branches() -> impl IntoView {
                                     <Icon icon=icondata::FaPlusSolid/>
                                 </button>
                             </li>
+                            <li>
+                                <button
+                                    on:click=move |_| set_importing.update(|x| *x = true)
+                                    class="block p-3 text-2xl text-white rounded-full bg-primary-600 hover:bg-primary-500 transition ease-out"
+                                >
+                                    <Icon icon=icondata::FaFileImportSolid/>
+                                </button>
+                            </li>
                         </ul>
                     }
                 }}
@@ -211,6 +314,36 @@ pub fn Branches() -> impl IntoView {
                 </MultiActionForm>
             </Card>
         </Modal>
+        <Modal
+            id="import_anki_modal"
+            show=importing
+            on_blur=move |_| set_importing.update(|x| *x = false)
+        >
+            <Card class="w-1/3 p-6">
+                <form on:submit=move |ev: SubmitEvent| {
+                    ev.prevent_default();
+                    let form = event_target::<HtmlFormElement>(&ev);
+                    import_package.dispatch(FormData::new_with_form(&form).unwrap());
+                }>
+                    <FormH1 text="Import from Anki"/>
+                    <FormInput
+                        input_type="file"
+                        id="Package"
+                        label="Anki package (.apkg)"
+                        placeholder=""
+                        name="package"
+                    />
+                    <FormSubmit msg="IMPORT"/>
+                </form>
+                {move || {
+                    import_package
+                        .value()
+                        .get()
+                        .and_then(Result::err)
+                        .map(|e| view! { <p class="mt-4 text-red-400">{e.to_string()}</p> })
+                }}
+            </Card>
+        </Modal>
     }
 }
 
@@ -218,19 +351,41 @@ pub fn Branches() -> impl IntoView {
 pub fn Branch() -> impl IntoView {
     let (editing, set_editing) = create_signal(false);
     let (adding_stem, set_adding_stem) = create_signal(false);
+    let (configuring, set_configuring) = create_signal(false);
 
     let edit_branch = create_server_multi_action::<EditBranch>();
+    let configure_branch = create_server_action::<ConfigureBranch>();
     let delete_branch = create_server_action::<DeleteBranch>();
     let add_stem = create_server_multi_action::<AddStem>();
 
+    // The modal stays open when the scheduling is rejected, to show why.
+    create_effect(move |_| {
+        if let Some(Ok(())) = configure_branch.value().get() {
+            set_configuring.update(|x| *x = false);
+        }
+    });
+
     let params = use_params::<BranchParams>();
     let id =
         move || params.with(|params| params.as_ref().map(|params| params.id).unwrap_or_default());
 
     let branch = create_resource(
-        move || (id(), edit_branch.version().get()),
+        move || {
+            (
+                id(),
+                edit_branch.version().get(),
+                configure_branch.version().get(),
+            )
+        },
         move |_| get_branch(id()),
     );
+    let config = move || {
+        branch
+            .get()
+            .and_then(Result::ok)
+            .map(|branch| branch.config().clone())
+            .unwrap_or_default()
+    };
 
     view! {
         <Transition fallback=move || {
@@ -259,11 +414,21 @@ pub fn Branch() -> impl IntoView {
                                                 size="5"
                                                 icon=icondata::FaPencilSolid
                                             />
+                                            <ControlBtn
+                                                on_click=move |_| set_configuring.update(|x| *x = true)
+                                                size="5"
+                                                icon=icondata::FaGearSolid
+                                            />
                                             <ControlBtn
                                                 on_click=move |_| set_adding_stem.update(|x| *x = true)
                                                 size="5"
                                                 icon=icondata::FaPlusSolid
                                             />
+                                            <ControlLink
+                                                href=move || format!("/export/branch/{}", id())
+                                                size="5"
+                                                icon=icondata::FaFileExportSolid
+                                            />
                                             <ControlAction
                                                 action=delete_branch
                                                 on_submit=move |_| {
@@ -310,6 +475,50 @@ pub fn Branch() -> impl IntoView {
                 </MultiActionForm>
             </Card>
         </Modal>
+        <Modal
+            id="configure_branch_modal"
+            show=configuring
+            on_blur=move |_| set_configuring.update(|x| *x = false)
+        >
+            <Card class="w-1/3 p-6">
+                <ActionForm action=configure_branch>
+                    <FormH1 text="Scheduling"/>
+                    <input type="hidden" name="id" value=id/>
+                    <FormInput
+                        input_type="text"
+                        id="DesiredRetention"
+                        label="Desired retention"
+                        placeholder="0.9"
+                        name="desired_retention"
+                        value=move || config().desired_retention.to_string()
+                    />
+                    <FormInput
+                        input_type="text"
+                        id="MaximumInterval"
+                        label="Maximum interval (days)"
+                        placeholder="36500"
+                        name="maximum_interval"
+                        value=move || config().maximum_interval.to_string()
+                    />
+                    <FormInput
+                        input_type="text"
+                        id="LearningSteps"
+                        label="Learning steps"
+                        placeholder="1m 10m"
+                        name="learning_steps"
+                        value=move || config().format_steps()
+                    />
+                    <FormSubmit msg="SAVE"/>
+                </ActionForm>
+                {move || {
+                    configure_branch
+                        .value()
+                        .get()
+                        .and_then(Result::err)
+                        .map(|e| view! { <p class="mt-4 text-red-400">{e.to_string()}</p> })
+                }}
+            </Card>
+        </Modal>
         <Modal
             id="add_stem_modal"
             show=adding_stem
@@ -337,14 +546,26 @@ pub fn Branch() -> impl IntoView {
 }
 
 #[component]
-pub fn BranchOverview(branch: Branch) -> impl IntoView {
+pub fn BranchOverview(branch: Branch, counts: Signal<LeafCounts>) -> impl IntoView {
+    let id = branch.id();
+
     view! {
-        <A
-            href=format!("/branch/{}", branch.id())
-            class="block py-6 px-9 text-2xl text-white rounded-xl outline outline-2 outline-secondary-750 hover:outline-primary-500 hover:scale-105 transition ease-out"
-        >
-            {branch.name()}
-        </A>
+        <div class="relative hover:scale-105 transition ease-out">
+            <A
+                href=format!("/branch/{}", id)
+                class="block py-6 px-9 space-y-2 text-2xl text-white rounded-xl outline outline-2 outline-secondary-750 hover:outline-primary-500"
+            >
+                <p>{branch.name()}</p>
+                <CountBadges counts/>
+            </A>
+            <Controls class="absolute -top-4 right-4">
+                <ControlA
+                    href=format!("/branch/{}/review", id)
+                    size="5"
+                    icon=icondata::FaBrainSolid
+                />
+            </Controls>
+        </div>
     }
 }
 
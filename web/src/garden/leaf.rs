@@ -1,39 +1,46 @@
 use crate::{
     error_template::ErrorTemplate,
-    ui::{Card, ControlAction, ControlBtn, Controls},
-    users::get_user,
+    ui::{
+        Card, ControlAction, ControlBtn, Controls, FormEditor, FormH1, FormSubmit, Modal, RichText,
+    },
 };
-use brainace_core::{Config, Leaf, Rating};
-use chrono::{DateTime, Utc};
+use brainace_core::{csv_file::RowError, queue::Scope, Direction, Leaf, LeafEdit, Rating, State};
 use leptos::{
-    component, create_resource, create_signal, leptos_server::Submission, server, view, Action,
-    CollectView, ErrorBoundary, IntoView, Params, ReadSignal, Resource, ServerFnError, SignalGet,
-    SignalUpdate, SignalWith, Transition,
+    component, create_resource, create_signal,
+    leptos_server::Submission,
+    server,
+    server_fn::codec::{MultipartData, MultipartFormData},
+    view, Action, CollectView, ErrorBoundary, IntoView, Params, ReadSignal, Resource,
+    ServerFnError, Signal, SignalGet, SignalUpdate, SignalWith, Transition,
 };
-use leptos_router::{use_params, Params, A};
+use leptos_router::{use_params, ActionForm, Params, A};
+use serde::{Deserialize, Serialize};
 
 #[server(GetLeaf, "/api")]
 pub async fn get_leaf(id: u32) -> Result<Leaf, ServerFnError> {
-    use crate::app::ssr::pool;
+    use crate::app::ssr::{pool, user};
     use brainace_core::SqlLeaf;
 
     let pool = pool()?;
+    user()?.authorize_leaf(id, &pool).await?;
 
     Ok(
         sqlx::query_as::<_, SqlLeaf>("SELECT * FROM leaves WHERE id = ?")
             .bind(id)
             .fetch_one(&pool)
             .await?
-            .into_leaf(),
+            .into_leaf()
+            .with_markup(),
     )
 }
 
 #[server(GetLeaves, "/api")]
 pub async fn get_leaves(stem_id: u32) -> Result<Vec<Leaf>, ServerFnError> {
-    use crate::app::ssr::pool;
+    use crate::app::ssr::{pool, user};
     use brainace_core::SqlLeaf;
 
     let pool = pool()?;
+    user()?.authorize_stem(stem_id, &pool).await?;
 
     Ok(
         sqlx::query_as::<_, SqlLeaf>("SELECT * FROM leaves WHERE stem_id = ?")
@@ -41,94 +48,303 @@ pub async fn get_leaves(stem_id: u32) -> Result<Vec<Leaf>, ServerFnError> {
             .fetch_all(&pool)
             .await?
             .iter()
-            .map(|leaf| leaf.into_leaf())
+            .map(|leaf| leaf.into_leaf().with_markup())
             .collect(),
     )
 }
 
-#[server(GetAllLeaves, "/api")]
-pub async fn get_all_leaves() -> Result<Vec<Leaf>, ServerFnError> {
-    use crate::app::ssr::pool;
-    use brainace_core::SqlLeaf;
+/// The largest page of due leaves sent at once.
+const MAX_PAGE: u32 = 100;
+
+/// A page of today's queue within `scope`, within what is left of the user's daily limits.
+#[server(GetDueLeaves, "/api")]
+pub async fn get_due_leaves(
+    scope: Scope,
+    offset: u32,
+    limit: u32,
+) -> Result<Vec<Leaf>, ServerFnError> {
+    use crate::app::ssr::{pool, user};
+    use brainace_core::queue::study_queue;
+    use chrono::Utc;
 
-    let user = get_user().await?;
     let pool = pool()?;
+    let user = user()?;
 
-    let id = match user {
-        Some(user) => user.id,
-        None => -1,
-    };
+    match scope {
+        Scope::All => {}
+        Scope::Branch(id) => user.authorize_branch(id, &pool).await?,
+        Scope::Stem(id) => user.authorize_stem(id, &pool).await?,
+    }
 
-    Ok(sqlx::query_as::<_, SqlLeaf>(
-        "SELECT l.* FROM leaves l
-            INNER JOIN stems s
-                ON s.id = l.stem_id
-            INNER JOIN branches b
-                ON b.id = s.branch_id
-                AND b.user_id = ?",
+    Ok(study_queue(
+        user.id,
+        scope,
+        Utc::now(),
+        offset,
+        limit.min(MAX_PAGE),
+        &pool,
     )
-    .bind(id)
-    .fetch_all(&pool)
     .await?
-    .iter()
-    .map(|leaf| leaf.into_leaf())
+    .into_iter()
+    .map(Leaf::with_markup)
     .collect())
 }
 
+/// Grows the leaves of a new front and back: one per cloze of the front, or a basic leaf.
 #[server(AddLeaf, "/api")]
 pub async fn add_leaf(stem_id: u32, front: String, back: String) -> Result<(), ServerFnError> {
-    use crate::app::ssr::pool;
+    use crate::app::ssr::{pool, user};
+    use brainace_core::Stem;
+    use chrono::Utc;
 
     let pool = pool()?;
+    user()?.authorize_stem(stem_id, &pool).await?;
 
-    let card = brainace_core::Card::default();
-    let card_json: sqlx::types::Json<brainace_core::Card> =
-        sqlx::types::Json::decode_from_string(&serde_json::to_string(&card).unwrap()).unwrap();
+    let leaves = Leaf::grow(&front, &back, Utc::now());
 
-    Ok(
-        sqlx::query("INSERT INTO leaves (stem_id, front, back, card) VALUES (?, ?, ?, ?)")
-            .bind(stem_id)
-            .bind(front)
-            .bind(back)
-            .bind(card_json)
-            .execute(&pool)
-            .await
-            .map(|_| ())?,
-    )
+    Ok(Stem::add_leaves(stem_id, &leaves, &pool).await?)
+}
+
+/// Rewrites the front and back of a leaf, keeping its schedule, along with the other clozes of its
+/// note, and tells which leaves were grown or pruned.
+#[server(EditLeaf, "/api")]
+pub async fn edit_leaf(id: u32, front: String, back: String) -> Result<LeafEdit, ServerFnError> {
+    use crate::app::ssr::{pool, user};
+    use chrono::Utc;
+
+    let pool = pool()?;
+    user()?.authorize_leaf(id, &pool).await?;
+
+    Ok(Leaf::edit(id, &front, &back, Utc::now(), &pool).await?)
+}
+
+/// Renders a side being written as it will be shown, with its clozes filled in.
+#[server(PreviewMarkup, "/api")]
+pub async fn preview_markup(text: String) -> Result<String, ServerFnError> {
+    use crate::app::ssr::user;
+    use brainace_core::markup::render_cloze;
+
+    user()?;
+
+    // No cloze is numbered 0, so each one reads as its answer.
+    Ok(render_cloze(&text, 0, true))
+}
+
+#[server(name = ImportLeaves, prefix = "/api", input = MultipartFormData)]
+pub async fn import_leaves(data: MultipartData) -> Result<(), ServerFnError> {
+    use crate::app::ssr::{pool, user};
+    use brainace_core::{deck_file::DeckFile, Stem};
+    use chrono::Utc;
+
+    let pool = pool()?;
+
+    let mut data = data
+        .into_inner()
+        .ok_or_else(|| ServerFnError::new("Missing form data."))?;
+
+    let mut stem_id = None;
+    let mut content = None;
+    while let Some(field) = data.next_field().await? {
+        match field.name().map(str::to_string).as_deref() {
+            Some("stem_id") => stem_id = Some(field.text().await?.parse::<u32>()?),
+            Some("deck") => content = Some(field.text().await?),
+            _ => {}
+        }
+    }
+
+    let stem_id = stem_id.ok_or_else(|| ServerFnError::new("Missing stem."))?;
+    let content = content.ok_or_else(|| ServerFnError::new("Missing deck file."))?;
+
+    user()?.authorize_stem(stem_id, &pool).await?;
+
+    let leaves: Vec<Leaf> = DeckFile::parse(&content)?
+        .into_decks(Utc::now())
+        .into_iter()
+        .flat_map(|deck| deck.leaves)
+        .collect();
+
+    Ok(Stem::add_leaves(stem_id, &leaves, &pool).await?)
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct CsvReport {
+    pub leaves: usize,
+    pub errors: Vec<RowError>,
+    pub imported: bool,
 }
 
+#[server(name = ImportLeavesCsv, prefix = "/api", input = MultipartFormData)]
+pub async fn import_leaves_csv(data: MultipartData) -> Result<CsvReport, ServerFnError> {
+    use crate::app::ssr::{pool, user};
+    use brainace_core::{
+        csv_file::{read_leaves, CsvOptions},
+        Stem,
+    };
+    use chrono::Utc;
+
+    let pool = pool()?;
+
+    let mut data = data
+        .into_inner()
+        .ok_or_else(|| ServerFnError::new("Missing form data."))?;
+
+    let mut stem_id = None;
+    let mut content = None;
+    let mut options = CsvOptions::default();
+    let mut dry_run = false;
+    while let Some(field) = data.next_field().await? {
+        match field.name().map(str::to_string).as_deref() {
+            Some("stem_id") => stem_id = Some(field.text().await?.parse::<u32>()?),
+            Some("file") => content = Some(field.text().await?),
+            Some("tsv") => options.delimiter = b'\t',
+            Some("no_headers") => options.has_headers = false,
+            Some("columns") => {
+                let columns = field.text().await?;
+                if !columns.trim().is_empty() {
+                    options.columns = Some(CsvOptions::parse_columns(&columns)?);
+                }
+            }
+            Some("dry_run") => dry_run = true,
+            _ => {}
+        }
+    }
+
+    let stem_id = stem_id.ok_or_else(|| ServerFnError::new("Missing stem."))?;
+    let content = content.ok_or_else(|| ServerFnError::new("Missing CSV file."))?;
+
+    user()?.authorize_stem(stem_id, &pool).await?;
+
+    let preview = read_leaves(&content, &options)?;
+    let count = preview.leaves.len();
+
+    // Nothing is written while a single row is invalid, so a fixed file can be imported again.
+    let imported = !dry_run && preview.errors.is_empty();
+    if imported {
+        let now = Utc::now();
+        let leaves: Vec<Leaf> = preview
+            .leaves
+            .into_iter()
+            .flat_map(|leaf| leaf.into_leaves(now))
+            .collect();
+
+        Stem::add_leaves(stem_id, &leaves, &pool).await?;
+    }
+
+    Ok(CsvReport {
+        leaves: count,
+        errors: preview.errors,
+        imported,
+    })
+}
+
+/// Rates the leaf in `direction` at the server's time, and returns the id of the review log and the
+/// version the leaf is at now. Fails unless the leaf is still at the `version` it was shown at, so
+/// a leaf reviewed or undone somewhere else in the meantime isn't rated twice.
 #[server(ReviewLeaf, "/api")]
 pub async fn review_leaf(
-    leaf: Leaf,
+    id: u32,
+    direction: Direction,
+    version: u32,
     rating: Rating,
-    now: DateTime<Utc>,
-) -> Result<(), ServerFnError> {
-    use crate::app::ssr::pool;
+) -> Result<(u32, u32), ServerFnError> {
+    use crate::app::ssr::{pool, user};
+    use brainace_core::{review_log::ReviewError, Config};
+    use chrono::Utc;
 
     let pool = pool()?;
+    let user = user()?;
+    user.authorize_leaf(id, &pool).await?;
 
-    let mut leaf = leaf;
+    let mut leaf = Leaf::get(id, &pool).await?.asked(direction);
+    if leaf.version() != version {
+        return Err(ReviewError::Conflict.into());
+    }
+    let config = Config::for_leaf(user.id, id, &pool).await?;
+    let log = leaf
+        .record_review(user.id, &config, rating, Utc::now(), &pool)
+        .await?;
 
-    let config = Config::default();
-    leaf.review(&config, rating, now);
+    Ok((log.id, leaf.version()))
+}
 
-    let card_json: sqlx::types::Json<brainace_core::Card> =
-        sqlx::types::Json::decode_from_string(&serde_json::to_string(leaf.card()).unwrap())
-            .unwrap();
+/// How long the leaf would wait in `direction` after each rating from now on, from Again to Easy.
+#[server(GetIntervals, "/api")]
+pub async fn get_intervals(id: u32, direction: Direction) -> Result<Vec<String>, ServerFnError> {
+    use crate::app::ssr::{pool, user};
+    use brainace_core::{config::format_interval, Config};
+    use chrono::Utc;
 
-    Ok(sqlx::query("UPDATE leaves SET card = $2 WHERE id = $1")
-        .bind(leaf.id())
-        .bind(card_json)
-        .execute(&pool)
-        .await
-        .map(|_| ())?)
+    let pool = pool()?;
+    let user = user()?;
+    user.authorize_leaf(id, &pool).await?;
+
+    let leaf = Leaf::get(id, &pool).await?.asked(direction);
+    let config = Config::for_leaf(user.id, id, &pool).await?;
+
+    Ok(leaf
+        .intervals(&config, Utc::now())
+        .into_iter()
+        .map(format_interval)
+        .collect())
+}
+
+/// Takes back the rating logged as `log_id`, restoring the card as it was before, and returns the
+/// version the leaf is at now. Fails if the leaf was rated again since.
+#[server(UndoReview, "/api")]
+pub async fn undo_review(log_id: u32) -> Result<u32, ServerFnError> {
+    use crate::app::ssr::{pool, user};
+    use brainace_core::review_log::ReviewLog;
+
+    let pool = pool()?;
+    let user = user()?;
+
+    let (_, version) = ReviewLog::undo(user.id, log_id, &pool)
+        .await?
+        .ok_or_else(|| {
+            ServerFnError::new("The rating could not be undone, as the leaf was changed since.")
+        })?;
+
+    Ok(version)
+}
+
+/// Starts asking the leaf from back to front as well, on a schedule of its own, or stops and
+/// forgets that schedule.
+#[server(SetBidirectional, "/api")]
+pub async fn set_bidirectional(id: u32, bidirectional: bool) -> Result<(), ServerFnError> {
+    use crate::app::ssr::{pool, user};
+    use brainace_core::Card;
+
+    let pool = pool()?;
+    user()?.authorize_leaf(id, &pool).await?;
+
+    // A leaf that already is bidirectional keeps its schedule, and cloze leaves are only asked from
+    // front to back. A new or dropped reverse schedule is a change to review conflicts.
+    let updated = sqlx::query(
+        "UPDATE leaves SET reverse_card = CASE WHEN $1 THEN COALESCE(reverse_card, $2) END,
+            version = CASE WHEN $1 = (reverse_card IS NOT NULL) THEN version ELSE version + 1 END
+            WHERE id = $3 AND (NOT $1 OR cloze IS NULL)",
+    )
+    .bind(bidirectional)
+    .bind(sqlx::types::Json(Card::default()))
+    .bind(id)
+    .execute(&pool)
+    .await?
+    .rows_affected();
+    if updated == 0 {
+        return Err(ServerFnError::new(
+            "Cloze leaves can only be asked from front to back.",
+        ));
+    }
+
+    Ok(())
 }
 
 #[server(DeleteLeaf, "/api")]
 pub async fn delete_leaf(id: u32) -> Result<(), ServerFnError> {
-    use crate::app::ssr::pool;
+    use crate::app::ssr::{pool, user};
 
     let pool = pool()?;
+    user()?.authorize_leaf(id, &pool).await?;
 
     Ok(sqlx::query("DELETE FROM leaves WHERE id = $1")
         .bind(id)
@@ -137,10 +353,30 @@ pub async fn delete_leaf(id: u32) -> Result<(), ServerFnError> {
         .map(|_| ())?)
 }
 
+/// Tells what an edit pruned, as the pruned leaves take their reviews with them.
+fn pruned_notice(edit: LeafEdit) -> Option<String> {
+    let pruned = match edit.pruned {
+        0 => return None,
+        1 => "1 leaf was".to_string(),
+        pruned => format!("{pruned} leaves were"),
+    };
+    let edited = if edit.edited_pruned {
+        " The edited leaf was one of them."
+    } else {
+        ""
+    };
+
+    Some(format!(
+        "{pruned} pruned with their reviews, as their clozes were taken out of the front.{edited}"
+    ))
+}
+
 #[component]
 pub fn Leaves(
-    leaves: Resource<(usize, usize), Result<Vec<Leaf>, ServerFnError>>,
+    leaves: Resource<(usize, usize, usize, usize, usize, usize), Result<Vec<Leaf>, ServerFnError>>,
+    edit_leaf: Action<EditLeaf, Result<LeafEdit, ServerFnError>>,
     delete_leaf: Action<DeleteLeaf, Result<(), ServerFnError>>,
+    set_bidirectional: Action<SetBidirectional, Result<(), ServerFnError>>,
     submissions: ReadSignal<Vec<Submission<AddLeaf, Result<(), ServerFnError>>>>,
 ) -> impl IntoView {
     view! {
@@ -170,7 +406,12 @@ pub fn Leaves(
                                                 .map(move |leaf| {
                                                     view! {
                                                         <li>
-                                                            <LeafOverview leaf delete_leaf/>
+                                                            <LeafOverview
+                                                                leaf
+                                                                edit_leaf
+                                                                delete_leaf
+                                                                set_bidirectional
+                                                            />
                                                         </li>
                                                     }
                                                 })
@@ -195,7 +436,16 @@ pub fn Leaves(
                             })
                             .collect_view()
                     };
+                    let pruned = move || {
+                        edit_leaf
+                            .value()
+                            .get()
+                            .and_then(Result::ok)
+                            .and_then(pruned_notice)
+                            .map(|notice| view! { <p class="mb-6 text-yellow-400">{notice}</p> })
+                    };
                     view! {
+                        {pruned}
                         <ul class="flex flex-col space-y-6">{existing_leaves} {pending_leaves}</ul>
                     }
                 }}
@@ -236,10 +486,17 @@ pub fn LeafDetails() -> impl IntoView {
                                 let state = serde_json::to_string(&card.state);
                                 let previous_state = serde_json::to_string(&card.previous_state);
                                 let log = serde_json::to_string(&card.log);
+                                let markup = leaf.markup().cloned().unwrap_or_default();
                                 view! {
                                     <div class="text-xl text-white">
-                                        <p>"Front: " {leaf.front()}</p>
-                                        <p>"Back: " {leaf.back()}</p>
+                                        <div class="flex space-x-2">
+                                            <p>"Front: "</p>
+                                            <RichText html=markup.revealed/>
+                                        </div>
+                                        <div class="flex space-x-2">
+                                            <p>"Back: "</p>
+                                            <RichText html=markup.answer/>
+                                        </div>
                                         <p>"Created at: " {leaf.created_at().to_string()}</p>
                                         <p>"Due: " {card.due.to_string()}</p>
                                         <p>"Stability: " {card.stability}</p>
@@ -265,46 +522,101 @@ pub fn LeafDetails() -> impl IntoView {
     }
 }
 
+/// The side a leaf is asked with, its cloze blanked until `revealed`.
+#[component]
+pub fn Front(leaf: Leaf, #[prop(into)] revealed: Signal<bool>) -> impl IntoView {
+    let markup = leaf.markup().cloned().unwrap_or_default();
+    let html = Signal::derive(move || {
+        if revealed() {
+            markup.revealed.clone()
+        } else {
+            markup.prompt.clone()
+        }
+    });
+
+    view! { <RichText html class="text-2xl text-center text-white hyphens-auto"/> }
+}
+
 #[component]
 pub fn Leaf(leaf: Leaf, revealed: ReadSignal<bool>) -> impl IntoView {
+    let answer = leaf
+        .markup()
+        .map(|markup| markup.answer.clone())
+        .unwrap_or_default();
+    let empty = leaf.answer().is_empty();
+
     view! {
         <Card class="mx-auto relative w-1/3">
             <div class="p-5">
-                <p class="text-2xl text-center text-white hyphens-auto">{leaf.front()}</p>
+                <Front leaf revealed/>
             </div>
-            <div class=("hidden", move || !revealed())>
+            <div class=("hidden", move || !revealed() || empty)>
                 <hr class="border-t-1 border-secondary-750"/>
                 <div class="p-5">
-                    <p class="text-2xl text-center text-primary-500 hyphens-auto">{leaf.back()}</p>
+                    <RichText
+                        html=answer
+                        class="text-2xl text-center text-primary-500 hyphens-auto"
+                    />
                 </div>
             </div>
         </Card>
     }
 }
 
+/// Where a schedule stands, such as "New" or "Review, due 2024-03-01".
+fn describe(card: &brainace_core::Card) -> String {
+    let state = match card.state {
+        State::New => return "New".to_string(),
+        State::Learning => "Learning",
+        State::Review => "Review",
+        State::Relearning => "Relearning",
+    };
+
+    format!("{state}, due {}", card.due.format("%Y-%m-%d"))
+}
+
 #[component]
 pub fn LeafOverview(
     leaf: Leaf,
+    edit_leaf: Action<EditLeaf, Result<LeafEdit, ServerFnError>>,
     delete_leaf: Action<DeleteLeaf, Result<(), ServerFnError>>,
+    set_bidirectional: Action<SetBidirectional, Result<(), ServerFnError>>,
 ) -> impl IntoView {
     let (hidden, set_hidden) = create_signal(true);
+    let (editing, set_editing) = create_signal(false);
 
     let id = leaf.id();
+    let front = leaf.front();
+    let back = leaf.back();
+    let answer = leaf
+        .markup()
+        .map(|markup| markup.answer.clone())
+        .unwrap_or_default();
+    let empty = leaf.back().is_empty();
+    let bidirectional = leaf.is_bidirectional();
+    let cloze = leaf.cloze().is_some();
+    let forward = describe(leaf.card());
+    let reverse = leaf.reverse_card().map(describe);
 
     view! {
         <Card class="mx-auto relative w-1/3 hover:scale-105 hover:border-primary-500 transition ease-out">
             <A href=format!("/leaf/{}", id)>
                 <div class="p-5">
-                    <p class="text-2xl text-center text-white hyphens-auto">{leaf.front()}</p>
+                    <Front leaf revealed=Signal::derive(move || !hidden())/>
                 </div>
-                <div class=("hidden", hidden)>
+                <div class=("hidden", move || hidden() || empty)>
                     <hr class="border-t-1 border-secondary-750"/>
                     <div class="p-5">
-                        <p class="text-2xl text-center text-primary-500 hyphens-auto">
-                            {leaf.back()}
-                        </p>
+                        <RichText
+                            html=answer
+                            class="text-2xl text-center text-primary-500 hyphens-auto"
+                        />
                     </div>
                 </div>
+                <div class="flex justify-center space-x-4 pb-3 text-sm text-secondary-370">
+                    <span>{format!("→ {forward}")}</span>
+                    {reverse.map(|reverse| view! { <span>{format!("← {reverse}")}</span> })}
+                </div>
             </A>
             <Controls class="absolute -top-4 right-4">
                 <ControlBtn
@@ -312,6 +624,30 @@ pub fn LeafOverview(
                     size="5"
                     icon=icondata::FaEyeRegular
                 />
+                <ControlBtn
+                    on_click=move |_| set_editing.update(|x| *x = true)
+                    size="5"
+                    icon=icondata::FaPencilSolid
+                />
+                {(!cloze)
+                    .then(|| {
+                        view! {
+                            <ControlAction
+                                action=set_bidirectional
+                                on_submit=move |_| {}
+                                size="5"
+                                icon=icondata::FaArrowRightArrowLeftSolid
+                            >
+                                <input type="hidden" name="id" value=id/>
+                                <input
+                                    type="hidden"
+                                    name="bidirectional"
+                                    value=(!bidirectional).to_string()
+                                />
+                            </ControlAction>
+                        }
+                    })}
+
                 <ControlAction
                     action=delete_leaf
                     on_submit=move |_| {}
@@ -322,6 +658,38 @@ pub fn LeafOverview(
                 </ControlAction>
             </Controls>
         </Card>
+        <Modal
+            id=&format!("edit_leaf_modal_{id}")
+            show=editing
+            on_blur=move |_| set_editing.update(|x| *x = false)
+        >
+            <Card class="w-1/3 p-6">
+                <ActionForm
+                    action=edit_leaf
+                    on:submit=move |_| set_editing.update(|x| *x = false)
+                >
+                    <FormH1 text="Editing leaf"/>
+                    <input type="hidden" name="id" value=id/>
+                    <FormEditor
+                        id=&format!("Front{id}")
+                        label="Front"
+                        placeholder="Front"
+                        name="front"
+                        preview=preview_markup
+                        value=front
+                    />
+                    <FormEditor
+                        id=&format!("Back{id}")
+                        label="Back"
+                        placeholder="Back"
+                        name="back"
+                        preview=preview_markup
+                        value=back
+                    />
+                    <FormSubmit msg="SAVE"/>
+                </ActionForm>
+            </Card>
+        </Modal>
     }
 }
 
@@ -1,22 +1,32 @@
 use crate::{
     error_template::ErrorTemplate,
-    garden::leaf::{get_leaves, AddLeaf, DeleteLeaf, Leaves},
-    ui::{Card, ControlAction, ControlBtn, Controls, FormH1, FormInput, FormSubmit, Modal},
+    garden::leaf::{
+        get_leaves, import_leaves, import_leaves_csv, preview_markup, AddLeaf, DeleteLeaf,
+        EditLeaf, Leaves, SetBidirectional,
+    },
+    ui::{
+        Card, ControlA, ControlAction, ControlBtn, ControlLink, Controls, CountBadges,
+        FormCheckbox, FormEditor, FormH1, FormInput, FormSubmit, Modal,
+    },
 };
-use brainace_core::Stem;
+use brainace_core::{queue::LeafCounts, Stem};
 use leptos::{
-    component, create_resource, create_server_action, create_server_multi_action, create_signal,
-    server, view, Action, CollectView, ErrorBoundary, IntoView, MultiAction, Params, ServerFnError,
-    SignalGet, SignalUpdate, SignalWith, Transition,
+    component, create_action, create_effect, create_resource, create_server_action,
+    create_server_multi_action, create_signal, ev::SubmitEvent, event_target, server, view, Action,
+    CollectView, ErrorBoundary, IntoView, MultiAction, Params, ServerFnError, Signal, SignalGet,
+    SignalUpdate, SignalWith, Transition,
 };
 use leptos_router::{use_navigate, use_params, MultiActionForm, Params, A};
+use std::collections::HashMap;
+use web_sys::{FormData, HtmlFormElement};
 
 #[server(GetStem, "/api")]
 pub async fn get_stem(id: u32) -> Result<Stem, ServerFnError> {
-    use crate::app::ssr::pool;
+    use crate::app::ssr::{pool, user};
     use brainace_core::SqlStem;
 
     let pool = pool()?;
+    user()?.authorize_stem(id, &pool).await?;
 
     Ok(
         sqlx::query_as::<_, SqlStem>("SELECT * FROM stems WHERE id = ?")
@@ -29,10 +39,11 @@ pub async fn get_stem(id: u32) -> Result<Stem, ServerFnError> {
 
 #[server(GetStems, "/api")]
 pub async fn get_stems(branch_id: u32) -> Result<Vec<Stem>, ServerFnError> {
-    use crate::app::ssr::pool;
+    use crate::app::ssr::{pool, user};
     use brainace_core::SqlStem;
 
     let pool = pool()?;
+    user()?.authorize_branch(branch_id, &pool).await?;
 
     Ok(
         sqlx::query_as::<_, SqlStem>("SELECT * FROM stems WHERE branch_id = ?")
@@ -45,11 +56,29 @@ pub async fn get_stems(branch_id: u32) -> Result<Vec<Stem>, ServerFnError> {
     )
 }
 
+/// Due, new and total leaves of each stem of the branch.
+#[server(GetStemCounts, "/api")]
+pub async fn get_stem_counts(branch_id: u32) -> Result<HashMap<u32, LeafCounts>, ServerFnError> {
+    use crate::app::ssr::{pool, user};
+    use brainace_core::settings::StudySettings;
+    use chrono::Utc;
+
+    let pool = pool()?;
+    let user = user()?;
+    user.authorize_branch(branch_id, &pool).await?;
+
+    let settings = StudySettings::for_user(user.id, &pool).await?;
+    let now = Utc::now();
+
+    Ok(LeafCounts::per_stem(branch_id, now, settings.day_end(now), &pool).await?)
+}
+
 #[server(AddStem, "/api")]
 pub async fn add_stem(branch_id: u32, name: String) -> Result<(), ServerFnError> {
-    use crate::app::ssr::pool;
+    use crate::app::ssr::{pool, user};
 
     let pool = pool()?;
+    user()?.authorize_branch(branch_id, &pool).await?;
 
     Ok(
         sqlx::query("INSERT INTO stems (branch_id, name) VALUES (?, ?)")
@@ -63,9 +92,10 @@ pub async fn add_stem(branch_id: u32, name: String) -> Result<(), ServerFnError>
 
 #[server(EditStem, "/api")]
 pub async fn edit_stem(id: u32, name: String) -> Result<(), ServerFnError> {
-    use crate::app::ssr::pool;
+    use crate::app::ssr::{pool, user};
 
     let pool = pool()?;
+    user()?.authorize_stem(id, &pool).await?;
 
     Ok(sqlx::query("UPDATE stems SET name = $2 WHERE id = $1")
         .bind(id)
@@ -77,9 +107,10 @@ pub async fn edit_stem(id: u32, name: String) -> Result<(), ServerFnError> {
 
 #[server(DeleteStem, "/api")]
 pub async fn delete_stem(id: u32) -> Result<(), ServerFnError> {
-    use crate::app::ssr::pool;
+    use crate::app::ssr::{pool, user};
 
     let pool = pool()?;
+    user()?.authorize_stem(id, &pool).await?;
 
     Ok(sqlx::query("DELETE FROM stems WHERE id = $1")
         .bind(id)
@@ -101,6 +132,10 @@ pub fn Stems(
         move || (add_stem.version().get(), delete_stem.version().get()),
         move |_| get_stems(branch_id),
     );
+    let counts = create_resource(
+        move || (add_stem.version().get(), delete_stem.version().get()),
+        move |_| get_stem_counts(branch_id),
+    );
 
     view! {
         <Transition fallback=move || view! { <p>"Loading..."</p> }>
@@ -126,9 +161,17 @@ pub fn Stems(
                                         stems
                                             .into_iter()
                                             .map(|stem| {
+                                                let id = stem.id();
+                                                let counts = Signal::derive(move || {
+                                                    counts
+                                                        .get()
+                                                        .and_then(Result::ok)
+                                                        .and_then(|counts| counts.get(&id).copied())
+                                                        .unwrap_or_default()
+                                                });
                                                 view! {
                                                     <li>
-                                                        <StemOverview stem=stem delete_stem=delete_stem/>
+                                                        <StemOverview stem=stem counts delete_stem=delete_stem/>
                                                     </li>
                                                 }
                                             })
@@ -170,11 +213,24 @@ struct StemParams {
 pub fn Stem() -> impl IntoView {
     let (editing, set_editing) = create_signal(false);
     let (adding_leaf, set_adding_leaf) = create_signal(false);
+    let (importing, set_importing) = create_signal(false);
+    let (importing_csv, set_importing_csv) = create_signal(false);
 
     let edit_stem = create_server_multi_action::<EditStem>();
     let delete_stem = create_server_action::<DeleteStem>();
     let add_leaf = create_server_multi_action::<AddLeaf>();
+    let edit_leaf = create_server_action::<EditLeaf>();
     let delete_leaf = create_server_action::<DeleteLeaf>();
+    let set_bidirectional = create_server_action::<SetBidirectional>();
+    let import_deck = create_action(|data: &FormData| import_leaves(data.clone().into()));
+    let import_csv = create_action(|data: &FormData| import_leaves_csv(data.clone().into()));
+
+    // The modal stays open when an import fails, to show why.
+    create_effect(move |_| {
+        if let Some(Ok(())) = import_deck.value().get() {
+            set_importing.update(|x| *x = false);
+        }
+    });
 
     let params = use_params::<StemParams>();
     let id =
@@ -186,7 +242,16 @@ pub fn Stem() -> impl IntoView {
     );
 
     let leaves = create_resource(
-        move || (add_leaf.version().get(), delete_leaf.version().get()),
+        move || {
+            (
+                add_leaf.version().get(),
+                edit_leaf.version().get(),
+                delete_leaf.version().get(),
+                set_bidirectional.version().get(),
+                import_deck.version().get(),
+                import_csv.version().get(),
+            )
+        },
         move |_| get_leaves(id()),
     );
     let submissions = add_leaf.submissions();
@@ -222,6 +287,31 @@ pub fn Stem() -> impl IntoView {
                                                 size="5"
                                                 icon=icondata::FaPlusSolid
                                             />
+                                            <ControlBtn
+                                                on_click=move |_| set_importing.update(|x| *x = true)
+                                                size="5"
+                                                icon=icondata::FaFileImportSolid
+                                            />
+                                            <ControlBtn
+                                                on_click=move |_| set_importing_csv.update(|x| *x = true)
+                                                size="5"
+                                                icon=icondata::FaFileCsvSolid
+                                            />
+                                            <ControlLink
+                                                href=move || format!("/export/stem/{}", id())
+                                                size="5"
+                                                icon=icondata::FaFileExportSolid
+                                            />
+                                            <ControlLink
+                                                href=move || format!("/export/stem/{}?format=csv", id())
+                                                size="5"
+                                                icon=icondata::FaTableSolid
+                                            />
+                                            <ControlLink
+                                                href=move || format!("/export/stem/{}?format=tsv", id())
+                                                size="5"
+                                                icon=icondata::FaTableColumnsSolid
+                                            />
                                             <ControlAction
                                                 action=delete_stem
                                                 on_submit=move |_| {
@@ -237,7 +327,9 @@ pub fn Stem() -> impl IntoView {
                                     </div>
                                     <Leaves
                                         leaves=leaves
+                                        edit_leaf=edit_leaf
                                         delete_leaf=delete_leaf
+                                        set_bidirectional=set_bidirectional
                                         submissions=submissions
                                     />
                                 }
@@ -284,40 +376,149 @@ pub fn Stem() -> impl IntoView {
                 >
                     <FormH1 text="Grow a leaf"/>
                     <input type="hidden" name="stem_id" value=id/>
-                    <FormInput
-                        input_type="text"
+                    <FormEditor
                         id="Front"
                         label="Front"
                         placeholder="Front"
                         name="front"
+                        preview=preview_markup
                     />
-                    <FormInput
-                        input_type="text"
+                    <FormEditor
                         id="Back"
                         label="Back"
                         placeholder="Back"
                         name="back"
+                        preview=preview_markup
                     />
                     <FormSubmit msg="ADD"/>
                 </MultiActionForm>
             </Card>
         </Modal>
+        <Modal
+            id="import_deck_modal"
+            show=importing
+            on_blur=move |_| set_importing.update(|x| *x = false)
+        >
+            <Card class="w-1/3 p-6">
+                <form on:submit=move |ev: SubmitEvent| {
+                    ev.prevent_default();
+                    let form = event_target::<HtmlFormElement>(&ev);
+                    import_deck.dispatch(FormData::new_with_form(&form).unwrap());
+                }>
+                    <FormH1 text="Import a deck"/>
+                    <input type="hidden" name="stem_id" value=id/>
+                    <FormInput
+                        input_type="file"
+                        id="Deck"
+                        label="Deck file (.ron, .json)"
+                        placeholder=""
+                        name="deck"
+                    />
+                    <FormSubmit msg="IMPORT"/>
+                </form>
+                {move || {
+                    import_deck
+                        .value()
+                        .get()
+                        .and_then(Result::err)
+                        .map(|e| view! { <p class="mt-4 text-red-400">{e.to_string()}</p> })
+                }}
+            </Card>
+        </Modal>
+        <Modal
+            id="import_csv_modal"
+            show=importing_csv
+            on_blur=move |_| set_importing_csv.update(|x| *x = false)
+        >
+            <Card class="w-1/3 p-6">
+                <form on:submit=move |ev: SubmitEvent| {
+                    ev.prevent_default();
+                    let form = event_target::<HtmlFormElement>(&ev);
+                    import_csv.dispatch(FormData::new_with_form(&form).unwrap());
+                }>
+                    <FormH1 text="Import a spreadsheet"/>
+                    <input type="hidden" name="stem_id" value=id/>
+                    <FormInput
+                        input_type="file"
+                        id="Spreadsheet"
+                        label="Spreadsheet (.csv, .tsv)"
+                        placeholder=""
+                        name="file"
+                    />
+                    <FormInput
+                        input_type="text"
+                        id="Columns"
+                        label="Columns"
+                        placeholder="front, back, due, stability, difficulty"
+                        name="columns"
+                    />
+                    <FormCheckbox label="Tab separated" name="tsv"/>
+                    <FormCheckbox label="No header row" name="no_headers"/>
+                    <FormCheckbox label="Preview only" name="dry_run"/>
+                    <FormSubmit msg="IMPORT"/>
+                </form>
+                {move || {
+                    import_csv
+                        .value()
+                        .get()
+                        .map(|report| match report {
+                            Err(e) => {
+                                view! { <p class="mt-4 text-red-400">{e.to_string()}</p> }
+                                    .into_view()
+                            }
+                            Ok(report) => {
+                                let summary = if report.imported {
+                                    format!("Imported {} leaves.", report.leaves)
+                                } else {
+                                    format!(
+                                        "{} leaves ready, {} rows with errors.",
+                                        report.leaves,
+                                        report.errors.len(),
+                                    )
+                                };
+                                view! {
+                                    <p class="mt-4 text-white">{summary}</p>
+                                    <ul class="mt-2 max-h-48 overflow-y-auto text-red-400">
+                                        {report
+                                            .errors
+                                            .into_iter()
+                                            .map(|error| {
+                                                view! {
+                                                    <li>"Line " {error.line} ": " {error.message}</li>
+                                                }
+                                            })
+                                            .collect_view()}
+                                    </ul>
+                                }
+                                    .into_view()
+                            }
+                        })
+                }}
+            </Card>
+        </Modal>
     }
 }
 
 #[component]
 pub fn StemOverview(
     stem: Stem,
+    counts: Signal<LeafCounts>,
     delete_stem: Action<DeleteStem, Result<(), ServerFnError>>,
 ) -> impl IntoView {
     let id = stem.id();
 
     view! {
         <Card class="mx-auto relative w-1/3 hover:scale-105 hover:border-primary-500 transition ease-out">
-            <A href=format!("/stem/{}", id) class="block p-5">
+            <A href=format!("/stem/{}", id) class="block p-5 space-y-2">
                 <p class="text-2xl text-center text-white hyphens-auto">{stem.name()}</p>
+                <CountBadges counts/>
             </A>
             <Controls class="absolute -top-4 right-4">
+                <ControlA
+                    href=format!("/stem/{}/review", id)
+                    size="5"
+                    icon=icondata::FaBrainSolid
+                />
                 <ControlBtn on_click=move |_| {} size="5" icon=icondata::FaPencilSolid/>
                 <ControlAction
                     action=delete_stem
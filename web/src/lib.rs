@@ -2,11 +2,18 @@ pub mod app;
 pub mod error_template;
 pub mod errors;
 #[cfg(feature = "ssr")]
+pub mod export;
+#[cfg(feature = "ssr")]
 pub mod fallback;
 pub mod garden;
+#[cfg(feature = "ssr")]
+pub mod media;
+pub mod profile;
 pub mod review;
+pub mod settings;
 #[cfg(feature = "ssr")]
 pub mod state;
+pub mod stats;
 pub mod ui;
 pub mod users;
 
@@ -1,18 +1,28 @@
 use axum::{
     body::Body as AxumBody,
-    extract::{Path, State},
+    extract::{DefaultBodyLimit, Path, State},
     http::Request,
     response::{IntoResponse, Response},
-    routing::get,
+    routing::{get, post},
     Router,
 };
 use axum_session::{SessionConfig, SessionLayer, SessionStore};
 use axum_session_auth::{AuthConfig, AuthSessionLayer, SessionSqlitePool};
-use brainace_core::auth::{AuthSession, User};
-use brainace_web::{app::*, fallback::file_and_error_handler, state::AppState};
+use brainace_core::{
+    auth::{AuthSession, User},
+    media::MAX_SIZE,
+};
+use brainace_web::{
+    app::*,
+    export::{export_branch, export_stem},
+    fallback::file_and_error_handler,
+    media::upload_media,
+    state::AppState,
+};
 use leptos::{get_configuration, logging::log, provide_context};
 use leptos_axum::{generate_route_list, handle_server_fns_with_context, LeptosRoutes};
 use sqlx::{sqlite::SqlitePoolOptions, SqlitePool};
+use std::path::PathBuf;
 
 async fn server_fn_handler(
     State(app_state): State<AppState>,
@@ -75,11 +85,15 @@ async fn main() {
     let leptos_options = conf.leptos_options;
     let addr = leptos_options.site_addr;
     let routes = generate_route_list(App);
+    let media_dir = std::env::var("BRAINACE_MEDIA_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from("media"));
 
     let app_state = AppState {
         leptos_options,
         pool: pool.clone(),
         routes: routes.clone(),
+        media_dir,
     };
 
     // build our application with a route
@@ -88,6 +102,13 @@ async fn main() {
             "/api/*fn_name",
             get(server_fn_handler).post(server_fn_handler),
         )
+        .route("/export/branch/:id", get(export_branch))
+        .route("/export/stem/:id", get(export_stem))
+        // Room is left for the rest of the multipart form around the file.
+        .route(
+            "/media",
+            post(upload_media).layer(DefaultBodyLimit::max(MAX_SIZE + 64 * 1024)),
+        )
         .leptos_routes_with_handler(routes, get(leptos_routes_handler))
         .fallback(file_and_error_handler)
         .layer(
@@ -0,0 +1,100 @@
+use crate::{export::access_status, state::AppState};
+use axum::{
+    extract::{Multipart, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+};
+use brainace_core::{
+    auth::AuthSession,
+    media::{self, Media, MediaError},
+};
+use sqlx::SqlitePool;
+use std::path::Path;
+
+/// Stores the `file` field of a multipart form, answering with the Markdown referring to it.
+pub async fn upload_media(
+    State(state): State<AppState>,
+    auth_session: AuthSession,
+    mut multipart: Multipart,
+) -> Result<String, (StatusCode, String)> {
+    let user = auth_session
+        .current_user
+        .ok_or_else(|| rejection(StatusCode::UNAUTHORIZED, "Unauthorized"))?;
+
+    while let Some(field) = multipart
+        .next_field()
+        .await
+        .map_err(|e| rejection(StatusCode::BAD_REQUEST, e))?
+    {
+        if field.name() != Some("file") {
+            continue;
+        }
+
+        let name = field.file_name().unwrap_or_default().to_string();
+        let mime = field.content_type().unwrap_or_default().to_string();
+        let content = field
+            .bytes()
+            .await
+            .map_err(|e| rejection(StatusCode::BAD_REQUEST, e))?;
+
+        let media = Media::new(&name, &mime, &content).map_err(|e| {
+            let status = match e {
+                MediaError::Unsupported(_) => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                MediaError::TooLarge => StatusCode::PAYLOAD_TOO_LARGE,
+                MediaError::Empty => StatusCode::BAD_REQUEST,
+            };
+            rejection(status, e)
+        })?;
+
+        let stored = media.clone();
+        tokio::task::spawn_blocking(move || stored.store(&content, &state.media_dir))
+            .await
+            .map_err(|e| rejection(StatusCode::INTERNAL_SERVER_ERROR, e))?
+            .map_err(|e| rejection(StatusCode::INTERNAL_SERVER_ERROR, e))?;
+        media
+            .insert(user.id, &state.pool)
+            .await
+            .map_err(|e| rejection(StatusCode::INTERNAL_SERVER_ERROR, e))?;
+
+        return Ok(media.reference());
+    }
+
+    Err(rejection(StatusCode::BAD_REQUEST, "Missing file."))
+}
+
+/// A failed upload, with a message the client can show.
+fn rejection(status: StatusCode, error: impl ToString) -> (StatusCode, String) {
+    (status, error.to_string())
+}
+
+/// Serves `/media/<hash>.<extension>` to the users who uploaded it.
+pub async fn serve_media(
+    file_name: &str,
+    auth_session: AuthSession,
+    pool: &SqlitePool,
+    dir: &Path,
+) -> Result<Response, StatusCode> {
+    let user = auth_session.current_user.ok_or(StatusCode::UNAUTHORIZED)?;
+    let (hash, _) = media::parse_file_name(file_name).ok_or(StatusCode::NOT_FOUND)?;
+    let media = Media::get(hash, user.id, pool)
+        .await
+        .map_err(access_status)?;
+
+    let content = tokio::fs::read(media::path(dir, &media.hash))
+        .await
+        .map_err(|_| StatusCode::NOT_FOUND)?;
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, media.mime),
+            // The content of a hash never changes.
+            (
+                header::CACHE_CONTROL,
+                "private, max-age=31536000, immutable".to_string(),
+            ),
+            (header::X_CONTENT_TYPE_OPTIONS, "nosniff".to_string()),
+        ],
+        content,
+    )
+        .into_response())
+}
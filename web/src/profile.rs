@@ -0,0 +1,133 @@
+use crate::{error_template::ErrorTemplate, stats::StatTile, ui::Card};
+use brainace_core::stats::{DayCount, Streaks};
+use chrono::Datelike;
+use leptos::{
+    component, create_resource, server, view, CollectView, ErrorBoundary, IntoView, ServerFnError,
+    SignalGet, Transition,
+};
+use serde::{Deserialize, Serialize};
+
+/// How many days the heatmap covers, a year of weeks.
+const HEATMAP_DAYS: u32 = 53 * 7;
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ProfileSummary {
+    pub username: String,
+    pub heatmap: Vec<DayCount>,
+    pub streaks: Streaks,
+    pub leaves: u32,
+}
+
+#[server(GetProfile, "/api")]
+pub async fn get_profile() -> Result<ProfileSummary, ServerFnError> {
+    use crate::app::ssr::{pool, user};
+    use brainace_core::{
+        settings::StudySettings,
+        stats::{heatmap, leaf_count, review_counts, study_days},
+    };
+    use chrono::Utc;
+
+    let pool = pool()?;
+    let user = user()?;
+
+    let settings = StudySettings::for_user(user.id, &pool).await?;
+    let today = settings.study_date(Utc::now());
+    let days = study_days(&review_counts(user.id, &pool).await?, &settings);
+
+    Ok(ProfileSummary {
+        username: user.username,
+        heatmap: heatmap(&days, today, HEATMAP_DAYS),
+        streaks: Streaks::from_days(&days, today),
+        leaves: leaf_count(user.id, &pool).await?,
+    })
+}
+
+#[component]
+pub fn Heatmap(days: Vec<DayCount>) -> impl IntoView {
+    let max = days.iter().map(|day| day.count).max().unwrap_or(0).max(1);
+    // Weeks start on Sunday, so the first column is padded up to the first day.
+    let padding = days
+        .first()
+        .map_or(0, |day| day.date.weekday().num_days_from_sunday());
+
+    view! {
+        <Card class="p-5 space-y-4">
+            <p class="text-xl text-white">"Reviews"</p>
+            <div class="grid grid-rows-7 grid-flow-col gap-1 w-fit">
+                {(0..padding).map(|_| view! { <div class="size-3"></div> }).collect_view()}
+                {days
+                    .into_iter()
+                    .map(|day| {
+                        let color = match day.count * 4 / max {
+                            _ if day.count == 0 => "bg-secondary-750",
+                            0 => "bg-primary-700",
+                            1 => "bg-primary-600",
+                            2 => "bg-primary-500",
+                            _ => "bg-primary-400",
+                        };
+                        view! {
+                            <div
+                                title=format!("{}: {}", day.date, day.count)
+                                class=format!("size-3 rounded-sm {color}")
+                            ></div>
+                        }
+                    })
+                    .collect_view()}
+            </div>
+        </Card>
+    }
+}
+
+#[component]
+pub fn Profile() -> impl IntoView {
+    let profile = create_resource(|| (), |_| get_profile());
+
+    view! {
+        <Transition fallback=move || {
+            view! { <p>"Loading..."</p> }
+        }>
+            <ErrorBoundary fallback=|errors| {
+                view! { <ErrorTemplate errors=errors/> }
+            }>
+                {move || {
+                    profile
+                        .get()
+                        .map(move |profile| match profile {
+                            Err(e) => {
+                                view! { <pre>"Server Error: " {e.to_string()}</pre> }.into_view()
+                            }
+                            Ok(profile) => {
+                                view! {
+                                    <div class="space-y-6">
+                                        <div class="flex items-center h-16 px-8 py-1 border-b-2 border-primary-500">
+                                            <p class="text-4xl font-bold text-white tracking-wide">
+                                                {profile.username}
+                                            </p>
+                                        </div>
+                                        <div class="grid grid-cols-3 gap-6">
+                                            <StatTile
+                                                label="CURRENT STREAK"
+                                                value=format!("{} days", profile.streaks.current)
+                                            />
+                                            <StatTile
+                                                label="LONGEST STREAK"
+                                                value=format!("{} days", profile.streaks.longest)
+                                            />
+                                            <StatTile
+                                                label="LEAVES"
+                                                value=profile.leaves.to_string()
+                                            />
+                                        </div>
+                                        <Heatmap days=profile.heatmap/>
+                                    </div>
+                                }
+                                    .into_view()
+                            }
+                        })
+                        .unwrap_or_default()
+                }}
+
+            </ErrorBoundary>
+        </Transition>
+    }
+}
@@ -1,27 +1,114 @@
 use crate::{
     error_template::ErrorTemplate,
-    garden::leaf::{get_all_leaves, review_leaf, Leaf},
+    errors::AppError,
+    garden::leaf::{get_due_leaves, get_intervals, review_leaf, undo_review, Leaf},
     ui::{ActionA, ActionBtn},
 };
-use brainace_core::{Leaf, Rating};
-use chrono::{Datelike, Utc};
+use brainace_core::{queue::Scope, Leaf, Rating};
 use leptos::{
-    component, create_resource, create_signal, spawn_local, view, ErrorBoundary, IntoView,
-    SignalGet, SignalUpdate, Transition, WriteSignal,
+    component, create_effect, create_memo, create_resource, create_signal, ev, on_cleanup, server,
+    spawn_local, store_value, view, window_event_listener, ErrorBoundary, Errors, IntoView,
+    MaybeSignal, Params, ServerFnError, Show, Signal, SignalGet, SignalGetUntracked, SignalUpdate,
+    SignalWith, SignalWithUntracked, StoredValue, Transition,
 };
+use leptos_router::{use_params, Params};
 
+/// Fits the FSRS weights to the user's review history. Returns the number of reviews learned from,
+/// or `None` when there were too few to replace the default weights.
+#[server(OptimizeParameters, "/api")]
+pub async fn optimize_parameters() -> Result<Option<usize>, ServerFnError> {
+    use crate::app::ssr::{pool, user};
+    use brainace_core::{
+        optimizer::{fitted_reviews, optimize, store_weights},
+        review_log::ReviewLog,
+    };
+    use chrono::Utc;
+
+    let pool = pool()?;
+    let user = user()?;
+
+    let logs = ReviewLog::for_user(user.id, &pool).await?;
+    let count = fitted_reviews(&logs);
+
+    // Fitting takes a while, so it must not hold up the async runtime.
+    let Some(weights) = tokio::task::spawn_blocking(move || optimize(&logs)).await? else {
+        return Ok(None);
+    };
+
+    store_weights(user.id, &weights, count, Utc::now(), &pool).await?;
+
+    Ok(Some(count))
+}
+
+/// How many due leaves are fetched at once.
+const PAGE: u32 = 50;
+
+#[derive(Params, PartialEq)]
+struct ReviewParams {
+    id: u32,
+}
+
+/// Reviews the branch or stem whose id is in the URL, or shows Not Found for a missing or invalid
+/// id rather than reviewing everything.
 #[component]
-pub fn ReviewBtn(
-    leaf: Leaf,
-    rating: Rating,
-    set_i: WriteSignal<usize>,
-    set_revealed: WriteSignal<bool>,
-) -> impl IntoView {
-    let msg = match rating {
-        Rating::Again => "AGAIN",
-        Rating::Hard => "HARD",
-        Rating::Good => "GOOD",
-        Rating::Easy => "EASY",
+fn ScopedReview(scope: fn(u32) -> Scope) -> impl IntoView {
+    let params = use_params::<ReviewParams>();
+    let scope = create_memo(move |_| {
+        params.with(|params| params.as_ref().ok().map(|params| scope(params.id)))
+    });
+
+    view! {
+        <Show
+            when=move || scope().is_some()
+            fallback=|| {
+                let mut errors = Errors::default();
+                errors.insert_with_default_key(AppError::NotFound);
+                view! { <ErrorTemplate outside_errors=errors/> }
+            }
+        >
+            <Review scope=Signal::derive(move || scope().unwrap_or_default())/>
+        </Show>
+    }
+}
+
+#[component]
+pub fn BranchReview() -> impl IntoView {
+    view! { <ScopedReview scope=Scope::Branch/> }
+}
+
+#[component]
+pub fn StemReview() -> impl IntoView {
+    view! { <ScopedReview scope=Scope::Stem/> }
+}
+
+/// What was done with a leaf of the session, so it can be undone.
+#[derive(Clone, Copy)]
+pub enum Step {
+    /// The leaf with this id was rated.
+    Rated(u32, StoredValue<Logged>),
+    Skipped,
+}
+
+/// Where the review of a rated leaf stands on the server.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Logged {
+    Pending,
+    /// Undone before the server answered, so it is undone as soon as it does.
+    Undone,
+    Done(u32),
+    Failed,
+}
+
+#[component]
+pub fn ReviewBtn<F>(rating: Rating, interval: Signal<Option<String>>, rate: F) -> impl IntoView
+where
+    F: Fn(Rating) + 'static,
+{
+    let (msg, hint) = match rating {
+        Rating::Again => ("AGAIN", "1"),
+        Rating::Hard => ("HARD", "2"),
+        Rating::Good => ("GOOD", "3"),
+        Rating::Easy => ("EASY", "4"),
     };
 
     let (color, hover_color) = match rating {
@@ -32,51 +119,220 @@ pub fn ReviewBtn(
     };
 
     view! {
-        <ActionBtn
-            msg
-            on_click=move |_| {
-                let leaf = leaf.clone();
-                spawn_local(async move {
-                    let _ = review_leaf(leaf, rating, Utc::now()).await;
-                });
-                set_revealed.update(|x| *x = false);
-                set_i.update(|i| *i += 1);
-            }
-
-            color
-            hover_color
-        />
+        <div class="flex flex-col items-center space-y-2">
+            <span class="h-6 text-white">{move || interval().unwrap_or_default()}</span>
+            <ActionBtn msg on_click=move |_| rate(rating) color hover_color hint/>
+        </div>
     }
 }
 
+/// Reviews the due leaves within `scope`, all of the user's leaves by default.
 #[component]
-pub fn Review() -> impl IntoView {
+pub fn Review(#[prop(optional, into)] scope: MaybeSignal<Scope>) -> impl IntoView {
     let (revealed, set_revealed) = create_signal(false);
     let (i, set_i) = create_signal(0);
 
-    let leaves = create_resource(|| (), move |_| get_all_leaves());
-
-    let due_today_leaves = move || {
-        leaves.get().map(|leaves| {
-            leaves.map(|leaves| {
-                leaves
-                    .into_iter()
-                    .filter(|leaf| {
-                        leaf.card().due.num_days_from_ce() <= Utc::now().num_days_from_ce()
-                    })
-                    .collect()
-            })
-        })
+    let (page, set_page) = create_signal(0);
+    let (skipped, set_skipped) = create_signal(0);
+    let (history, set_history) = create_signal(Vec::<Step>::new());
+    let (error, set_error) = create_signal(None::<String>);
+
+    // Reviewed leaves leave the queue, so the next page starts after the skipped ones.
+    let leaves = create_resource(
+        move || (scope(), page()),
+        move |(scope, _)| get_due_leaves(scope, skipped.get_untracked(), PAGE),
+    );
+
+    let restart = move || {
+        set_revealed.update(|x| *x = false);
+        set_i.update(|i| *i = 0);
+        set_page.update(|page| *page = 0);
+        set_skipped.update(|skipped| *skipped = 0);
+        set_history.update(Vec::clear);
     };
+
+    // Another branch or stem starts its own queue.
+    create_effect(move |previous: Option<Scope>| {
+        let scope = scope();
+        if previous.is_some_and(|previous| previous != scope) {
+            restart();
+        }
+        scope
+    });
+
     let length = move || {
-        due_today_leaves()
-            .unwrap_or(Ok(Vec::new()))
-            .unwrap_or_default()
-            .len()
+        leaves
+            .get()
+            .and_then(Result::ok)
+            .map_or(0, |leaves| leaves.len())
+    };
+
+    create_effect(move |_| {
+        if length() == PAGE as usize && i() >= length() {
+            set_i.update(|i| *i = 0);
+            set_page.update(|page| *page += 1);
+            // Leaves of earlier pages are not kept around to be shown again.
+            set_history.update(Vec::clear);
+        }
+    });
+
+    // A failed rating or undo shows why, and the queue is fetched again, showing the leaf as it is
+    // now.
+    let fail = move |e: ServerFnError| {
+        set_error.update(|error| *error = Some(e.to_string()));
+
+        let first_page = page.get_untracked() == 0;
+        restart();
+        if first_page {
+            leaves.refetch();
+        }
+    };
+
+    // Rating or undoing changes the leaf, so the next rating of either of its directions has to
+    // name its new version.
+    let catch_up = move |id: u32, version: u32| {
+        leaves.update(|leaves| {
+            if let Some(Ok(leaves)) = leaves {
+                for leaf in leaves.iter_mut().filter(|leaf| leaf.id() == id) {
+                    leaf.set_version(version);
+                }
+            }
+        })
+    };
+    let undone = move |id: u32, version: Result<u32, ServerFnError>| match version {
+        Ok(version) => catch_up(id, version),
+        Err(e) => fail(e),
+    };
+
+    let undo = move || {
+        let Some(step) = history.with_untracked(|history| history.last().copied()) else {
+            return;
+        };
+
+        set_history.update(|history| {
+            history.pop();
+        });
+        match step {
+            Step::Rated(id, logged) => match logged.get_value() {
+                Logged::Done(log_id) => spawn_local(async move {
+                    undone(id, undo_review(log_id).await);
+                }),
+                Logged::Pending => logged.set_value(Logged::Undone),
+                Logged::Undone | Logged::Failed => {}
+            },
+            Step::Skipped => set_skipped.update(|skipped| *skipped -= 1),
+        }
+        set_revealed.update(|x| *x = false);
+        set_i.update(|i| *i -= 1);
+    };
+
+    // The leaf being reviewed, read from event handlers.
+    let current = move || {
+        leaves.with_untracked(|leaves| {
+            leaves
+                .as_ref()
+                .and_then(|leaves| leaves.as_ref().ok())
+                .and_then(|leaves| leaves.get(i.get_untracked()).cloned())
+        })
+    };
+
+    let rate = move |rating: Rating| {
+        let Some(leaf) = current() else {
+            return;
+        };
+
+        let id = leaf.id();
+        let direction = leaf.direction();
+        let version = leaf.version();
+        let logged = store_value(Logged::Pending);
+        set_history.update(|history| history.push(Step::Rated(id, logged)));
+        set_error.update(|error| *error = None);
+        spawn_local(async move {
+            match review_leaf(id, direction, version, rating).await {
+                Ok((log_id, _)) if logged.get_value() == Logged::Undone => {
+                    undone(id, undo_review(log_id).await);
+                }
+                Ok((log_id, version)) => {
+                    logged.set_value(Logged::Done(log_id));
+                    catch_up(id, version);
+                }
+                Err(e) => {
+                    logged.set_value(Logged::Failed);
+                    fail(e);
+                }
+            }
+        });
+        set_revealed.update(|x| *x = false);
+        set_i.update(|i| *i += 1);
+    };
+
+    let skip = move || {
+        if current().is_none() {
+            return;
+        }
+
+        set_history.update(|history| history.push(Step::Skipped));
+        set_skipped.update(|skipped| *skipped += 1);
+        set_i.update(|i| *i += 1);
+    };
+
+    // Space reveals, 1 to 4 rate, S skips, and U or Ctrl+Z (Cmd+Z) undoes.
+    let handle = window_event_listener(ev::keydown, move |event| {
+        if event.repeat() || event.alt_key() {
+            return;
+        }
+
+        let key = event.key();
+        let modified = event.ctrl_key() || event.meta_key();
+        if (key == "u" && !modified) || (key == "z" && modified) {
+            event.prevent_default();
+            undo();
+            return;
+        }
+        if modified || current().is_none() {
+            return;
+        }
+
+        let revealed = revealed.get_untracked();
+        match key.as_str() {
+            " " if !revealed => set_revealed.update(|x| *x = true),
+            "s" if !revealed => skip(),
+            "1" if revealed => rate(Rating::Again),
+            "2" if revealed => rate(Rating::Hard),
+            "3" if revealed => rate(Rating::Good),
+            "4" if revealed => rate(Rating::Easy),
+            _ => return,
+        }
+        event.prevent_default();
+    });
+    on_cleanup(move || handle.remove());
+
+    let leaf = move || {
+        leaves
+            .get()
+            .map(|leaves| leaves.map(|leaves| leaves.get(i()).cloned()))
+    };
+
+    let asked = move || {
+        leaf()
+            .and_then(Result::ok)
+            .flatten()
+            .map(|leaf| (leaf.id(), leaf.direction()))
+    };
+    let intervals = create_resource(asked, |asked| async move {
+        match asked {
+            Some((id, direction)) => get_intervals(id, direction).await.ok(),
+            None => None,
+        }
+    });
+    let interval = move |rating: Rating| {
+        Signal::derive(move || {
+            intervals
+                .get()
+                .flatten()
+                .and_then(|intervals| intervals.get(rating as usize - 1).cloned())
+        })
     };
-    let leaf =
-        move || due_today_leaves().map(|leaves| leaves.map(|leaves| leaves.get(i()).cloned()));
-    let leaf_unwrap = move || leaf().unwrap().unwrap().unwrap();
 
     view! {
         <Transition fallback=move || view! { <p class="text-white">"Loading..."</p> }>
@@ -94,6 +350,10 @@ pub fn Review() -> impl IntoView {
                         {move || format!("{} / {}", i(), length())}
                     </label>
                 </header>
+                {move || {
+                    error().map(|error| view! { <p class="w-3/5 mx-auto text-red-400">{error}</p> })
+                }}
+
                 <main class="w-3/5 mx-auto flex-1 flex justify-center items-center">
                     {move || {
                         if i() >= length() {
@@ -146,41 +406,40 @@ pub fn Review() -> impl IntoView {
                                 view! {
                                     <div class="w-full flex justify-center space-x-12">
                                         <ReviewBtn
-                                            leaf=leaf_unwrap()
                                             rating=Rating::Again
-                                            set_i
-                                            set_revealed
+                                            interval=interval(Rating::Again)
+                                            rate
                                         />
                                         <ReviewBtn
-                                            leaf=leaf_unwrap()
                                             rating=Rating::Hard
-                                            set_i
-                                            set_revealed
+                                            interval=interval(Rating::Hard)
+                                            rate
                                         />
                                         <ReviewBtn
-                                            leaf=leaf_unwrap()
                                             rating=Rating::Good
-                                            set_i
-                                            set_revealed
+                                            interval=interval(Rating::Good)
+                                            rate
                                         />
                                         <ReviewBtn
-                                            leaf=leaf_unwrap()
                                             rating=Rating::Easy
-                                            set_i
-                                            set_revealed
+                                            interval=interval(Rating::Easy)
+                                            rate
                                         />
                                     </div>
                                 }
                             } else {
                                 view! {
                                     <div class="w-full flex justify-between">
-                                        <ActionBtn
-                                            msg="SKIP"
-                                            on_click=move |_| set_i.update(|i| *i += 1)
-                                        />
+                                        <div class="flex space-x-4">
+                                            <ActionBtn msg="SKIP" on_click=move |_| skip() hint="S"/>
+                                            <Show when=move || history.with(|history| !history.is_empty())>
+                                                <ActionBtn msg="UNDO" on_click=move |_| undo() hint="U"/>
+                                            </Show>
+                                        </div>
                                         <ActionBtn
                                             msg="REVEAL"
                                             on_click=move |_| set_revealed.update(|x| *x = true)
+                                            hint="Space"
                                         />
                                     </div>
                                 }
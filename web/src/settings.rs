@@ -0,0 +1,114 @@
+use crate::{
+    error_template::ErrorTemplate,
+    ui::{Card, FormH1, FormInput, FormSubmit},
+};
+use brainace_core::settings::StudySettings;
+use leptos::{
+    component, create_resource, create_server_action, server, view, ErrorBoundary, IntoView,
+    ServerFnError, SignalGet, Transition,
+};
+use leptos_router::ActionForm;
+
+#[server(GetStudySettings, "/api")]
+pub async fn get_study_settings() -> Result<StudySettings, ServerFnError> {
+    use crate::app::ssr::{pool, user};
+
+    let pool = pool()?;
+    let user = user()?;
+
+    Ok(StudySettings::for_user(user.id, &pool).await?)
+}
+
+#[server(SaveStudySettings, "/api")]
+pub async fn save_study_settings(
+    new_per_day: String,
+    reviews_per_day: String,
+    day_starts_at: String,
+    timezone: String,
+) -> Result<(), ServerFnError> {
+    use crate::app::ssr::{pool, user};
+
+    let pool = pool()?;
+    let user = user()?;
+
+    let settings = StudySettings::parse(&new_per_day, &reviews_per_day, &day_starts_at, &timezone)?;
+
+    Ok(settings.save(user.id, &pool).await?)
+}
+
+#[component]
+pub fn Settings() -> impl IntoView {
+    let save_settings = create_server_action::<SaveStudySettings>();
+
+    let settings = create_resource(
+        move || save_settings.version().get(),
+        move |_| get_study_settings(),
+    );
+
+    view! {
+        <Transition fallback=move || {
+            view! { <p>"Loading..."</p> }
+        }>
+            <ErrorBoundary fallback=|errors| {
+                view! { <ErrorTemplate errors=errors/> }
+            }>
+                {move || {
+                    settings
+                        .get()
+                        .map(move |settings| match settings {
+                            Err(e) => {
+                                view! { <pre>"Server Error: " {e.to_string()}</pre> }.into_view()
+                            }
+                            Ok(settings) => {
+                                view! {
+                                    <div class="flex justify-center">
+                                        <Card class="w-1/3 p-6">
+                                            <ActionForm action=save_settings>
+                                                <FormH1 text="Study settings"/>
+                                                <FormInput
+                                                    input_type="text"
+                                                    id="NewPerDay"
+                                                    label="New leaves per day"
+                                                    placeholder="20"
+                                                    name="new_per_day"
+                                                    value=settings.new_per_day.to_string()
+                                                />
+                                                <FormInput
+                                                    input_type="text"
+                                                    id="ReviewsPerDay"
+                                                    label="Reviews per day"
+                                                    placeholder="200"
+                                                    name="reviews_per_day"
+                                                    value=settings.reviews_per_day.to_string()
+                                                />
+                                                <FormInput
+                                                    input_type="text"
+                                                    id="DayStartsAt"
+                                                    label="Day starts at (hour)"
+                                                    placeholder="4"
+                                                    name="day_starts_at"
+                                                    value=settings.day_starts_at.to_string()
+                                                />
+                                                <FormInput
+                                                    input_type="text"
+                                                    id="Timezone"
+                                                    label="Timezone"
+                                                    placeholder="Europe/Paris"
+                                                    name="timezone"
+                                                    value=settings.timezone
+                                                />
+                                                <FormSubmit msg="SAVE"/>
+                                            </ActionForm>
+                                        </Card>
+                                    </div>
+                                }
+                                    .into_view()
+                            }
+                        })
+                        .unwrap_or_default()
+                }}
+
+            </ErrorBoundary>
+        </Transition>
+    }
+}
@@ -2,6 +2,7 @@ use axum::extract::FromRef;
 use leptos::LeptosOptions;
 use leptos_router::RouteListing;
 use sqlx::SqlitePool;
+use std::path::PathBuf;
 
 /// This takes advantage of Axum's SubStates feature by deriving FromRef. This is the only way to have more than one
 /// item in Axum's State. Leptos requires you to have leptosOptions in your State struct for the leptos route handlers
@@ -10,4 +11,6 @@ pub struct AppState {
     pub leptos_options: LeptosOptions,
     pub pool: SqlitePool,
     pub routes: Vec<RouteListing>,
+    /// Where attached media is stored, from `BRAINACE_MEDIA_DIR`.
+    pub media_dir: PathBuf,
 }
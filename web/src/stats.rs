@@ -0,0 +1,212 @@
+use crate::{error_template::ErrorTemplate, ui::Card};
+use brainace_core::stats::{CardStats, DayCount};
+use leptos::{
+    component, create_resource, server, view, CollectView, ErrorBoundary, IntoView, ServerFnError,
+    SignalGet, Transition,
+};
+
+/// How far back or ahead a series goes at most.
+const MAX_DAYS: u32 = 365;
+
+/// How many days the dashboard charts.
+const DAYS: u32 = 30;
+
+/// The number of reviews on each of the last `days` study days, oldest first.
+#[server(GetReviewsPerDay, "/api")]
+pub async fn get_reviews_per_day(days: u32) -> Result<Vec<DayCount>, ServerFnError> {
+    use crate::app::ssr::{pool, user};
+    use brainace_core::{review_log::ReviewLog, settings::StudySettings, stats::reviews_per_day};
+    use chrono::{Duration, Utc};
+
+    let pool = pool()?;
+    let user = user()?;
+
+    let days = days.clamp(1, MAX_DAYS);
+    let settings = StudySettings::for_user(user.id, &pool).await?;
+    let today = settings.study_date(Utc::now());
+    let since = settings.start_of(today - Duration::days(i64::from(days) - 1));
+    let logs = ReviewLog::since(user.id, since, &pool).await?;
+
+    Ok(reviews_per_day(&logs, &settings, today, days))
+}
+
+/// The share of reviews recalled over the last `days` study days.
+#[server(GetRetention, "/api")]
+pub async fn get_retention(days: u32) -> Result<Option<f64>, ServerFnError> {
+    use crate::app::ssr::{pool, user};
+    use brainace_core::{review_log::ReviewLog, settings::StudySettings, stats::true_retention};
+    use chrono::{Duration, Utc};
+
+    let pool = pool()?;
+    let user = user()?;
+
+    let days = days.clamp(1, MAX_DAYS);
+    let settings = StudySettings::for_user(user.id, &pool).await?;
+    let today = settings.study_date(Utc::now());
+    let since = settings.start_of(today - Duration::days(i64::from(days) - 1));
+
+    Ok(true_retention(
+        &ReviewLog::since(user.id, since, &pool).await?,
+    ))
+}
+
+/// The number of leaves due on each of the next `days` study days, from today.
+#[server(GetForecast, "/api")]
+pub async fn get_forecast(days: u32) -> Result<Vec<DayCount>, ServerFnError> {
+    use crate::app::ssr::{pool, user};
+    use brainace_core::{
+        settings::StudySettings,
+        stats::{cards, forecast},
+    };
+    use chrono::Utc;
+
+    let pool = pool()?;
+    let user = user()?;
+
+    let settings = StudySettings::for_user(user.id, &pool).await?;
+    let today = settings.study_date(Utc::now());
+
+    Ok(forecast(
+        &cards(user.id, &pool).await?,
+        &settings,
+        today,
+        days.clamp(1, MAX_DAYS),
+    ))
+}
+
+#[server(GetCardStats, "/api")]
+pub async fn get_card_stats() -> Result<CardStats, ServerFnError> {
+    use crate::app::ssr::{pool, user};
+    use brainace_core::stats::cards;
+
+    let pool = pool()?;
+    let user = user()?;
+
+    Ok(CardStats::from_cards(&cards(user.id, &pool).await?))
+}
+
+#[component]
+pub fn StatTile(label: &'static str, value: String) -> impl IntoView {
+    view! {
+        <Card class="p-5">
+            <p class="text-sm text-secondary-370 tracking-wide">{label}</p>
+            <p class="text-3xl font-bold text-white">{value}</p>
+        </Card>
+    }
+}
+
+#[component]
+pub fn BarChart(title: &'static str, series: Vec<DayCount>) -> impl IntoView {
+    let max = series.iter().map(|day| day.count).max().unwrap_or(0).max(1);
+    let total: u32 = series.iter().map(|day| day.count).sum();
+
+    view! {
+        <Card class="p-5 space-y-4">
+            <div class="flex justify-between">
+                <p class="text-xl text-white">{title}</p>
+                <p class="text-xl text-secondary-370">{total}</p>
+            </div>
+            <div class="h-40 flex items-end space-x-1">
+                {series
+                    .into_iter()
+                    .map(|day| {
+                        view! {
+                            <div
+                                title=format!("{}: {}", day.date, day.count)
+                                class="flex-1 rounded-t bg-primary-500 hover:bg-primary-400"
+                                style=format!("height: {}%", day.count * 100 / max)
+                            ></div>
+                        }
+                    })
+                    .collect_view()}
+            </div>
+        </Card>
+    }
+}
+
+#[component]
+pub fn Stats() -> impl IntoView {
+    let reviews = create_resource(|| (), |_| get_reviews_per_day(DAYS));
+    let retention = create_resource(|| (), |_| get_retention(DAYS));
+    let forecast = create_resource(|| (), |_| get_forecast(DAYS));
+    let card_stats = create_resource(|| (), |_| get_card_stats());
+
+    view! {
+        <Transition fallback=move || {
+            view! { <p>"Loading..."</p> }
+        }>
+            <ErrorBoundary fallback=|errors| {
+                view! { <ErrorTemplate errors=errors/> }
+            }>
+                <div class="space-y-6">
+                    <div class="grid grid-cols-3 gap-6">
+                        {move || {
+                            retention
+                                .get()
+                                .map(|retention| {
+                                    retention
+                                        .map(|retention| {
+                                            let value = retention
+                                                .map_or("-".to_string(), |retention| {
+                                                    format!("{:.1}%", retention * 100.0)
+                                                });
+                                            view! { <StatTile label="TRUE RETENTION" value/> }
+                                        })
+                                })
+                        }}
+                        {move || {
+                            card_stats
+                                .get()
+                                .map(|stats| {
+                                    stats
+                                        .map(|stats| {
+                                            let stability = stats
+                                                .average_stability
+                                                .map_or("-".to_string(), |stability| {
+                                                    format!("{stability:.1}d")
+                                                });
+                                            let states = format!(
+                                                "{} / {} / {} / {}",
+                                                stats.new,
+                                                stats.learning,
+                                                stats.review,
+                                                stats.relearning,
+                                            );
+                                            view! {
+                                                <StatTile label="AVERAGE STABILITY" value=stability/>
+                                                <StatTile
+                                                    label="NEW / LEARNING / REVIEW / RELEARNING"
+                                                    value=states
+                                                />
+                                            }
+                                        })
+                                })
+                        }}
+
+                    </div>
+                    {move || {
+                        reviews
+                            .get()
+                            .map(|series| {
+                                series
+                                    .map(|series| {
+                                        view! { <BarChart title="Reviews per day" series/> }
+                                    })
+                            })
+                    }}
+                    {move || {
+                        forecast
+                            .get()
+                            .map(|series| {
+                                series
+                                    .map(|series| {
+                                        view! { <BarChart title="Due in the next 30 days" series/> }
+                                    })
+                            })
+                    }}
+
+                </div>
+            </ErrorBoundary>
+        </Transition>
+    }
+}
@@ -1,18 +1,36 @@
+use brainace_core::{media::accepted_types, queue::LeafCounts};
 use icondata::Icon;
 use leptos::{
-    component,
+    component, create_effect, create_node_ref, create_resource, create_signal,
     ev::{MouseEvent, SubmitEvent},
-    event_target,
+    event_target, event_target_value,
+    html::Div,
+    request_animation_frame,
     server_fn::{
         client::Client, codec::PostUrl, error::NoCustomError, request::ClientReq, ServerFn,
     },
-    view, Action, AttributeValue, Children, IntoView, ReadSignal, Serializable, ServerFnError,
+    set_timeout_with_handle, store_value, view, Action, AttributeValue, Children, IntoView,
+    ReadSignal, Serializable, ServerFnError, Signal, SignalGet, SignalWith, TimeoutHandle,
 };
 use leptos_icons::*;
 use leptos_router::{ActionForm, A};
 use serde::de::DeserializeOwned;
+use std::{future::Future, time::Duration};
+use wasm_bindgen::{prelude::wasm_bindgen, JsValue};
 use web_sys::{Element, FormData};
 
+#[wasm_bindgen]
+extern "C" {
+    /// Typesets the math of `element` with KaTeX, from `public/typeset.js`.
+    #[wasm_bindgen(js_name = typesetMath, catch)]
+    fn typeset_math(element: &Element) -> Result<(), JsValue>;
+
+    /// Uploads the file picked in `input` and inserts its reference in the field with id
+    /// `target`, from `public/media.js`.
+    #[wasm_bindgen(js_name = attachMedia, catch)]
+    fn attach_media(input: &Element, target: &str) -> Result<JsValue, JsValue>;
+}
+
 #[component]
 pub fn SideBar(children: Children) -> impl IntoView {
     view! {
@@ -117,6 +135,52 @@ pub fn Card<'a>(children: Children, #[prop(optional)] class: Option<&'a str>) ->
     view! { <div class=class>{children()}</div> }
 }
 
+/// HTML rendered and sanitized by the server, such as the [`Markup`](brainace_core::markup::Markup)
+/// of a leaf, its math typeset once shown.
+#[component]
+pub fn RichText(
+    #[prop(into)] html: Signal<String>,
+    #[prop(optional)] class: Option<&'static str>,
+) -> impl IntoView {
+    let node = create_node_ref::<Div>();
+
+    create_effect(move |_| {
+        html.track();
+        if let Some(node) = node.get() {
+            // The new HTML is only in place on the next frame.
+            request_animation_frame(move || {
+                let _ = typeset_math(&node);
+            });
+        }
+    });
+
+    view! {
+        <div
+            class=format!("markup {}", class.unwrap_or_default())
+            inner_html=html
+            node_ref=node
+        ></div>
+    }
+}
+
+/// The number of due, new and total leaves of a branch or a stem.
+#[component]
+pub fn CountBadges(counts: Signal<LeafCounts>) -> impl IntoView {
+    view! {
+        <div class="flex justify-center space-x-2 text-sm">
+            <span class="px-2 rounded-full bg-primary-500 text-white" title="Due">
+                {move || counts().due}
+            </span>
+            <span class="px-2 rounded-full bg-blue-500 text-white" title="New">
+                {move || counts().new}
+            </span>
+            <span class="px-2 rounded-full bg-secondary-750 text-white" title="Total">
+                {move || counts().total}
+            </span>
+        </div>
+    }
+}
+
 #[component]
 pub fn Modal<'a, F: Fn(MouseEvent) + 'static>(
     id: &'a str,
@@ -171,6 +235,32 @@ where
     }
 }
 
+#[component]
+pub fn ControlLink<'a, F>(href: F, size: &'a str, icon: Icon) -> impl IntoView
+where
+    F: Fn() -> String + 'static,
+{
+    view! {
+        <a
+            href=href
+            download
+            rel="external"
+            class="group block size-8 p-1.5 text-white hover:bg-primary-500"
+        >
+            <Icon icon=icon class=format!("size-{} group-hover:scale-105", size)/>
+        </a>
+    }
+}
+
+#[component]
+pub fn ControlA<'a>(href: String, size: &'a str, icon: Icon) -> impl IntoView {
+    view! {
+        <A href=href class="group block size-8 p-1.5 text-white hover:bg-primary-500">
+            <Icon icon=icon class=format!("size-{} group-hover:scale-105", size)/>
+        </A>
+    }
+}
+
 #[component]
 pub fn ControlAction<'a, I, O, F>(
     action: Action<I, Result<O, ServerFnError>>,
@@ -212,11 +302,17 @@ pub fn ActionBtn<'a, F>(
     on_click: F,
     #[prop(optional)] color: Option<&'a str>,
     #[prop(optional)] hover_color: Option<&'a str>,
+    /// The keyboard shortcut of the button.
+    #[prop(optional)]
+    hint: Option<&'a str>,
 ) -> impl IntoView
 where
     F: FnMut(MouseEvent) + 'static,
 {
     let msg = msg.to_string();
+    let hint = hint.map(|hint| {
+        view! { <kbd class="ml-2 px-1.5 rounded bg-black/20 text-sm font-mono">{hint.to_string()}</kbd> }
+    });
     let color = color.unwrap_or("bg-primary-500").to_string();
     let hover_color = hover_color.unwrap_or("hover:bg-primary-400").to_string();
 
@@ -229,6 +325,7 @@ where
         >
 
             {msg}
+            {hint}
         </button>
     }
 }
@@ -263,6 +360,7 @@ pub fn FormInput<'a>(
     placeholder: &'a str,
     name: &'a str,
     #[prop(optional, into)] maxlength: Option<AttributeValue>,
+    #[prop(optional, into)] value: Option<AttributeValue>,
 ) -> impl IntoView {
     let input_type = input_type.to_string();
     let id = id.to_string();
@@ -281,6 +379,7 @@ pub fn FormInput<'a>(
                 placeholder=placeholder
                 name=name
                 maxlength=maxlength
+                value=value
                 class="w-full p-2 rounded-md bg-transparent text-white outline outline-2 outline-primary-500 caret-primary-400 selection:bg-primary-400 focus:outline-offset-2 focus:outline-primary-300 transition-all ease-out"
             />
         </div>
@@ -310,6 +409,87 @@ pub fn FormCheckbox<'a>(label: &'a str, name: &'a str) -> impl IntoView {
     }
 }
 
+/// How long typing has to pause before the preview of a [`FormEditor`] is rendered again.
+const PREVIEW_DELAY: Duration = Duration::from_millis(300);
+
+/// A multi-line field for a side of a leaf, with a live preview of how `preview` renders it and
+/// a way to attach media to it.
+#[component]
+pub fn FormEditor<'a, F, Fut>(
+    id: &'a str,
+    label: &'a str,
+    placeholder: &'a str,
+    name: &'a str,
+    preview: F,
+    #[prop(optional, into)] value: Option<String>,
+) -> impl IntoView
+where
+    F: Fn(String) -> Fut + 'static,
+    Fut: Future<Output = Result<String, ServerFnError>> + 'static,
+{
+    let id = id.to_string();
+    let label = label.to_string();
+    let placeholder = placeholder.to_string();
+    let name = name.to_string();
+
+    // The preview is only asked for once typing pauses, not on every keystroke.
+    let (text, set_text) = create_signal(value.clone().unwrap_or_default());
+    let typing = store_value(None::<TimeoutHandle>);
+    let on_input = move |text: String| {
+        if let Some(handle) = typing.get_value() {
+            handle.clear();
+        }
+        typing.set_value(set_timeout_with_handle(move || set_text(text), PREVIEW_DELAY).ok());
+    };
+    let rendered = create_resource(text, preview);
+    let html = Signal::derive(move || rendered.get().and_then(Result::ok).unwrap_or_default());
+
+    view! {
+        <div class="mb-4">
+            <label for=id.clone() class="block mb-2 text-lg font-bold text-white">
+                {label}
+            </label>
+            <textarea
+                id=id.clone()
+                placeholder=placeholder
+                name=name
+                rows="4"
+                on:input=move |ev| on_input(event_target_value(&ev))
+                class="w-full p-2 rounded-md bg-transparent text-white font-mono resize-y outline outline-2 outline-primary-500 caret-primary-400 selection:bg-primary-400 focus:outline-offset-2 focus:outline-primary-300 transition-all ease-out"
+            >
+                {value}
+            </textarea>
+            <RichText html class="min-h-10 mt-2 p-2 rounded-md bg-secondary-870 text-white"/>
+            <FormAttach label="Attach an image or audio clip" target=&id/>
+        </div>
+    }
+}
+
+/// Attaches an image or audio clip to the field with id `target`, which gets the Markdown
+/// referring to it.
+#[component]
+pub fn FormAttach<'a>(label: &'a str, target: &'a str) -> impl IntoView {
+    let label = label.to_string();
+    let target = target.to_string();
+
+    view! {
+        <div class="mt-2">
+            <label class="block mb-2 text-sm font-bold text-white">
+                {label}
+                <input
+                    type="file"
+                    accept=accepted_types()
+                    on:change=move |ev| {
+                        let _ = attach_media(&event_target::<Element>(&ev), &target);
+                    }
+
+                    class="block mt-1 text-secondary-250 file:mr-4 file:px-3 file:py-1 file:rounded-md file:border-0 file:bg-secondary-750 file:text-white hover:file:bg-secondary-630"
+                />
+            </label>
+        </div>
+    }
+}
+
 #[component]
 pub fn FormSubmit<'a>(msg: &'a str) -> impl IntoView {
     let msg = msg.to_string();
@@ -1,10 +1,14 @@
-use crate::ui::{
-    ActionA, Card, FormCheckbox, FormH1, FormInput, FormSubmit, SideBarAction, SideBarItem,
-    SideBarItemCircle, SideBarItems,
+use crate::{
+    review::OptimizeParameters,
+    ui::{
+        ActionA, Card, FormCheckbox, FormH1, FormInput, FormSubmit, SideBarAction, SideBarItem,
+        SideBarItemCircle, SideBarItems,
+    },
 };
-use brainace_core::auth::User;
+use brainace_core::{auth::User, optimizer::MIN_REVIEWS};
 use leptos::{
-    component, server, view, Action, IntoView, Resource, ServerFnError, SignalGet, Suspense,
+    component, create_server_action, server, view, Action, IntoView, Resource, ServerFnError,
+    SignalGet, Suspense,
 };
 use leptos_router::{ActionForm, A};
 
@@ -185,6 +189,24 @@ pub fn LoginSection(
     user: Resource<(usize, usize, usize), Result<Option<User>, ServerFnError>>,
     logout: Action<Logout, Result<(), ServerFnError>>,
 ) -> impl IntoView {
+    let optimize = create_server_action::<OptimizeParameters>();
+    let optimize_result = move || {
+        let (message, class) = if optimize.pending().get() {
+            ("Optimizing...".to_string(), "text-secondary-250")
+        } else {
+            match optimize.value().get()? {
+                Ok(Some(count)) => (format!("Fitted to {count} reviews"), "text-secondary-250"),
+                Ok(None) => (
+                    format!("At least {MIN_REVIEWS} reviews are needed"),
+                    "text-secondary-250",
+                ),
+                Err(e) => (e.to_string(), "text-red-400"),
+            }
+        };
+
+        Some(view! { <li class=format!("px-4 text-sm {class}")>{message}</li> })
+    };
+
     let login_signup_buttons = move || {
         view! {
             <SideBarItems>
@@ -208,6 +230,12 @@ pub fn LoginSection(
                                         icon=icondata::FaUserSolid
                                         text="PROFILE"
                                     />
+                                    <SideBarAction
+                                        action=optimize
+                                        icon=icondata::FaWandMagicSparklesSolid
+                                        text="OPTIMIZE"
+                                    />
+                                    {optimize_result}
                                     <SideBarAction
                                         action=logout
                                         icon=icondata::FiLogOut